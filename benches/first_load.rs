@@ -0,0 +1,75 @@
+//! Measures the latency of the first async load of fresh assets against a
+//! later load and the cost of starting a thread, which a pool spawning its
+//! workers on the first load would add
+//!
+//! Run with `cargo bench --bench first_load`
+
+use assets::{Asset, AssetHandle, AssetLoadError, Assets, LoadableAsset};
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+struct Text(String);
+
+impl Asset for Text {}
+
+impl LoadableAsset for Text {
+    fn load(path: &Path) -> Result<Self, AssetLoadError> {
+        Ok(Text(fs::read_to_string(path)?))
+    }
+}
+
+const ROUNDS: u32 = 200;
+
+fn load(assets: &mut Assets, path: &Path) -> Duration {
+    let start = Instant::now();
+    let handle: AssetHandle<Text> = assets.load(path, false);
+    assets.block_until_loaded(handle.clone(), None);
+    let elapsed = start.elapsed();
+    assert!(assets.get(handle).is_some_and(|text| !text.0.is_empty()));
+    elapsed
+}
+
+fn main() {
+    let dir = std::env::temp_dir().join(format!("assets-first-load-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let first = dir.join("first");
+    let second = dir.join("second");
+    fs::write(&first, "first").unwrap();
+    fs::write(&second, "second").unwrap();
+
+    let mut create = Duration::ZERO;
+    let mut first_load = Duration::ZERO;
+    let mut later_load = Duration::ZERO;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        let mut assets = Assets::with_workers(4);
+        create += start.elapsed();
+        first_load += load(&mut assets, &first);
+        later_load += load(&mut assets, &second);
+    }
+
+    // give the workers time to start before loading
+    let mut started_load = Duration::ZERO;
+    for _ in 0..ROUNDS {
+        let mut assets = Assets::with_workers(4);
+        std::thread::sleep(Duration::from_millis(1));
+        started_load += load(&mut assets, &first);
+    }
+
+    let mut spawn = Duration::ZERO;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        std::thread::spawn(|| {}).join().unwrap();
+        spawn += start.elapsed();
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+    println!("create assets:               {:?}", create / ROUNDS);
+    println!("first load after creation:   {:?}", first_load / ROUNDS);
+    println!("first load, started workers: {:?}", started_load / ROUNDS);
+    println!("later load:                  {:?}", later_load / ROUNDS);
+    println!("start thread:                {:?}", spawn / ROUNDS);
+}
@@ -0,0 +1,51 @@
+//! Compares get_many with calling get per handle
+//!
+//! Run with `cargo bench --bench get_many`
+
+use assets::{Asset, AssetHandle, Assets};
+use std::{hint::black_box, time::Instant};
+
+struct Texture(u64);
+
+impl Asset for Texture {}
+
+const HANDLES: usize = 500;
+const ROUNDS: u32 = 2000;
+
+fn main() {
+    let mut assets = Assets::new();
+    let handles: Vec<AssetHandle<Texture>> = (0..HANDLES)
+        .map(|i| assets.insert(Texture(i as u64)))
+        .collect();
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        let sum: u64 = handles
+            .iter()
+            .map(|handle| assets.get(handle.clone()).map_or(0, |texture| texture.0))
+            .sum();
+        black_box(sum);
+    }
+    let get = start.elapsed();
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        let sum: u64 = assets
+            .get_many(&handles)
+            .into_iter()
+            .map(|texture| texture.map_or(0, |texture| texture.0))
+            .sum();
+        black_box(sum);
+    }
+    let get_many = start.elapsed();
+
+    let lookups = (HANDLES as u32 * ROUNDS) as f64;
+    println!(
+        "get:      {:.1} ns per asset",
+        get.as_nanos() as f64 / lookups
+    );
+    println!(
+        "get_many: {:.1} ns per asset",
+        get_many.as_nanos() as f64 / lookups
+    );
+}
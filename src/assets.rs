@@ -1,99 +1,866 @@
-use crate::handle::AssetHandle;
+use crate::error::{AssetError, AssetLoadError, ConvertError, GetError, WatchError};
+use crate::glob;
+use crate::handle::{AssetHandle, HandleKey, HandleRef, WeakAssetHandle};
+use crate::limiter::{ByteLimiter, BytePermit, LoadLimiter};
+#[cfg(feature = "async")]
+use crate::oneshot;
+use crate::pool::WorkerPool;
+use crate::snapshot::{self, SnapshotEntry, SnapshotableAsset};
+use crate::source::{
+    AssetSource, FsSource, LoadContext, RangeReadableSource, read_file, read_file_to_string,
+    with_context,
+};
 use std::any::TypeId;
-use std::sync::atomic::{AtomicU64, Ordering::SeqCst};
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering::SeqCst};
 use std::{
     any::Any,
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     fs,
     path::{Path, PathBuf},
-    sync::{Arc, mpsc},
-    time::Duration,
+    sync::{Arc, Mutex, Weak, mpsc},
+    time::{Duration, Instant, SystemTime},
 };
 
 pub type DynAsset = Box<dyn Asset>;
+pub type SharedAsset = Arc<dyn Asset>;
 pub type DynRenderAsset = ArcHandle<dyn Any + Send + Sync>;
-pub type DynAssetLoadFn = Box<dyn Fn(&Path) -> DynAsset>;
-pub type DynAssetWriteFn = Box<dyn Fn(&mut DynAsset, &Path)>;
+pub type DynAssetLoadFn = Box<dyn Fn(&Path) -> Result<DynAsset, AssetLoadError> + Send>;
+pub type DynAssetWriteFn = Box<dyn Fn(&mut dyn Asset, &Path) + Send>;
+pub type DynAssetRemoveFn = Box<dyn Fn(&dyn Asset) + Send>;
+pub type DynAssetLoadedFn = Box<dyn FnOnce(&dyn Asset) + Send>;
+pub type DynAssetWrittenFn = Box<dyn FnOnce(Result<(), std::io::Error>) + Send>;
+pub type MultiAssetLoadFn = fn(&Path) -> Result<Vec<(String, DynAsset)>, AssetLoadError>;
+pub type PreprocessorFn = Arc<dyn Fn(&Path, Vec<u8>) -> Vec<u8> + Send + Sync>;
+pub type ReloadInterceptorFn = Box<dyn Fn(&ReloadContext) -> ReloadDecision + Send>;
+type ConvertedAsset = (RenderKey, (u64, u64), Result<DynRenderAsset, ConvertError>);
 
 pub trait Asset: Any + Send + Sync {}
 
-pub trait LoadableAsset {
-    fn load(path: &Path) -> Self;
+pub trait LoadableAsset: Sized {
+    fn load(path: &Path) -> Result<Self, AssetLoadError>;
+}
+
+/// Asset which can be created from bytes in memory instead of a file
+pub trait LoadableFromBytes: Asset + Sized {
+    fn from_bytes(bytes: &[u8]) -> Result<Self, AssetLoadError>;
+}
+/// Asset which reads only the parts of a file it needs, e.g. one mesh out of a large pack
+pub trait RangedLoadableAsset: Asset + Sized {
+    fn load_ranged(path: &Path, source: &dyn RangeReadableSource) -> Result<Self, AssetLoadError>;
+}
+
+/// Settings read from the `.import` file next to an asset
+pub trait ImportSettings: Default {
+    fn parse(content: &str) -> Self;
+}
+
+/// Asset loaded with per file import settings
+pub trait ConfigurableLoadableAsset: Sized {
+    type ImportSettings: ImportSettings;
+
+    fn load(path: &Path, settings: &Self::ImportSettings) -> Result<Self, AssetLoadError>;
+}
+
+/// Asset whose file starts with a `version N` line and is migrated to the current version on load
+///
+/// Files without the line count as version 1
+pub trait VersionedAsset: Sized {
+    fn current_version() -> u32;
+    /// Migrate data from version `from` to version `from + 1`
+    fn migrate(from: u32, data: Vec<u8>) -> Vec<u8>;
+    /// Parse data of the current version
+    fn parse(data: &[u8]) -> Result<Self, AssetLoadError>;
+    /// Write migrated files back to disk at the current version
+    fn rewrite_migrated() -> bool {
+        false
+    }
+}
+
+/// Asset with a placeholder used until the real asset is loaded, e.g. a 1x1 white pixel
+pub trait DefaultAsset: Asset {
+    fn default_asset() -> Self;
+}
+
+/// File containing several assets told apart by labels, e.g. the meshes of a scene
+pub trait MultiAsset {
+    fn load_all(path: &Path) -> Result<Vec<(String, DynAsset)>, AssetLoadError>;
 }
+
+/// Asset which can report how much memory it holds, summed up in [`Assets::stats`]
+pub trait MeasurableAsset: Asset {
+    fn byte_size(&self) -> usize;
+}
+
 pub trait WriteableAsset {
     fn write(&mut self, _path: &Path);
 }
 
+/// Asset which only grows at the end and is appended to its file instead of overwritten
+pub trait AppendableAsset {
+    /// Number of entries in the asset
+    fn entries(&self) -> usize;
+    /// Write the entries from `from` onwards, the file is opened in append mode
+    fn append(&self, from: usize, file: &mut fs::File);
+}
+
 pub trait RenderAsset: Any {}
 
-pub trait ConvertableRenderAsset: RenderAsset + Send + Sync {
+pub trait ConvertableRenderAsset: RenderAsset + Send + Sync + Sized {
     type SourceAsset: Asset;
-    type Params;
+    type Params: ParamsKey;
+
+    fn convert(source: &Self::SourceAsset, params: &Self::Params) -> Result<Self, ConvertError>;
+}
+
+/// Render asset converted from the render asset of a previous stage
+///
+/// E.g. `Shader` -> `ParsedShader` -> `GpuShader` where `ParsedShader` is the
+/// previous stage of `GpuShader`
+pub trait ConvertStage: RenderAsset + Send + Sync + Sized {
+    type Previous: ConvertableRenderAsset;
+    type Params: ParamsKey;
+
+    fn convert(previous: &Self::Previous, params: &Self::Params) -> Result<Self, ConvertError>;
+}
+
+/// Key params contribute to the render cache key
+///
+/// Implemented for every `Hash` type. Implement it directly for params which can not be
+/// hashed, e.g. by quantizing floats. Params with the same key share one render asset,
+/// so a key which collides for different params returns the wrong render asset
+pub trait ParamsKey {
+    fn params_key(&self) -> u64;
+}
+
+impl<T: Hash> ParamsKey for T {
+    fn params_key(&self) -> u64 {
+        let mut hasher = std::hash::DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+// render assets are cached per source, render asset type and params
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct RenderKey {
+    source: AssetHandle<DynAsset>,
+    ty: TypeId,
+    params: u64,
+}
+
+impl RenderKey {
+    fn new<G: ConvertableRenderAsset>(source: AssetHandle<DynAsset>, params: &G::Params) -> Self {
+        Self {
+            source,
+            ty: TypeId::of::<G>(),
+            params: params.params_key(),
+        }
+    }
+}
+
+struct LoadedAsset {
+    // position in the scheduled order, None for awaited loads delivered outside of it
+    sequence: Option<u64>,
+    handle: AssetHandle<DynAsset>,
+    asset: Result<DynAsset, AssetLoadError>,
+    content_hash: Option<u64>,
+    modified: Option<SystemTime>,
+    bytes: Option<BytePermit>,
+}
+
+impl LoadedAsset {
+    // result of a load cancelled before it started, discarded by poll_loaded
+    fn cancelled(sequence: Option<u64>, handle: AssetHandle<DynAsset>) -> Self {
+        Self {
+            sequence,
+            handle,
+            asset: Err(AssetLoadError::Cancelled),
+            content_hash: None,
+            modified: None,
+            bytes: None,
+        }
+    }
+}
+
+/// Render cache shared between several [`Assets`] instances
+///
+/// Render assets of sources loaded from a file are keyed by the canonical path and the
+/// content hash, so instances only share render assets of identical content. Sources
+/// which were inserted or modified are keyed per instance and not shared. Every
+/// conversion and invalidation locks the cache
+#[derive(Default)]
+pub struct SharedRenderCache {
+    entries: HashMap<(SourceKey, TypeId, u64), DynRenderAsset>,
+}
+
+impl SharedRenderCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum SourceKey {
+    // canonical path and content hash
+    Content(PathBuf, u64),
+    // instance and handle id
+    Instance(u64, u64),
+}
+
+/// Counters for tuning the watcher debounce
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WatcherStats {
+    /// Events received from the watcher
+    pub raw_events: u64,
+    /// Reloads performed after merging events for the same asset
+    pub coalesced_reloads: u64,
+    /// Events for paths no asset is watching
+    pub dropped: u64,
+    /// Events for files whose content did not change
+    pub unchanged: u64,
+    /// Paths currently watched by the watcher
+    pub watched_paths: usize,
+}
+
+/// Whether watching a directory includes its subdirectories
+///
+/// Files are watched non recursively and directories recursively unless chosen
+/// through [`Assets::watch_with_mode`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchMode {
+    Recursive,
+    NonRecursive,
+}
+
+impl From<WatchMode> for notify_debouncer_mini::notify::RecursiveMode {
+    fn from(mode: WatchMode) -> Self {
+        match mode {
+            WatchMode::Recursive => Self::Recursive,
+            WatchMode::NonRecursive => Self::NonRecursive,
+        }
+    }
+}
+
+/// Number of assets held by [`Assets`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AssetStats {
+    /// Loaded or inserted cpu assets
+    pub assets: usize,
+    /// Converted render assets
+    pub render_assets: usize,
+    /// Paths currently watched for hot reloading
+    pub watched_paths: usize,
+    /// Assets waiting to be written by poll_write
+    pub dirty: usize,
+    /// Async loads not delivered yet
+    pub pending: usize,
+    /// Summed size of assets whose type is registered as measurable
+    pub bytes: usize,
+}
+
+/// A pending reload passed to the reload interceptor
+pub struct ReloadContext<'a> {
+    /// The path the assets are loaded from
+    pub path: &'a Path,
+    /// The handles which would reload
+    pub handles: &'a [AssetHandle<DynAsset>],
+    cache: &'a HashMap<AssetHandle<DynAsset>, SharedAsset>,
+}
+
+impl ReloadContext<'_> {
+    /// Get the currently loaded value of a handle, before the reload
+    pub fn previous<T: Asset>(&self, handle: &AssetHandle<T>) -> Option<&T> {
+        self.cache
+            .get(&handle.clone_typed::<DynAsset>())
+            .and_then(|asset| asset.as_any().downcast_ref::<T>())
+    }
+}
+
+/// What to do with a pending reload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReloadDecision {
+    /// Reload the assets now
+    Apply,
+    /// Drop the reload, the assets keep their current value
+    Skip,
+    /// Queue the reload again for the next poll_reload
+    Defer,
+}
+
+/// How an asset is loaded
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LoadOptions {
+    /// Load on the calling thread instead of in the background
+    pub sync: bool,
+    /// Reload when the file changes
+    pub watch: bool,
+    /// Write to the file when modified
+    pub write: bool,
+}
+
+/// Chainable options for a load, created by [`Assets::load_with`]
+pub struct LoadBuilder<'a, T> {
+    assets: &'a mut Assets,
+    path: PathBuf,
+    options: LoadOptions,
+    ty: PhantomData<T>,
+}
+
+impl<T: Asset + LoadableAsset + WriteableAsset> LoadBuilder<'_, T> {
+    /// Reload when the file changes
+    pub fn watch(mut self, watch: bool) -> Self {
+        self.options.watch = watch;
+        self
+    }
+
+    /// Write to the file when modified
+    pub fn write(mut self, write: bool) -> Self {
+        self.options.write = write;
+        self
+    }
+
+    /// Load on the calling thread instead of in the background
+    pub fn sync(mut self, sync: bool) -> Self {
+        self.options.sync = sync;
+        self
+    }
+
+    /// Start the load
+    pub fn build(self) -> AssetHandle<T> {
+        self.assets.load_with_options(&self.path, self.options)
+    }
+}
+
+/// What to do when a completed load has the id of an explicitly inserted asset
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum IdCollisionPolicy {
+    /// The loaded asset replaces the inserted one
+    #[default]
+    LastWriterWins,
+    /// The loaded asset is discarded
+    Reject,
+}
+
+/// Load state of a handle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetState {
+    /// The first async load has not been delivered by poll_loaded yet
+    Loading,
+    Loaded,
+    /// The loader returned an error, see [`Assets::load_error`]
+    Failed,
+    /// The file does not exist, or the handle does not point to an asset
+    NotFound,
+}
+
+/// Assets that changed since the last [`Assets::changed`]
+#[derive(Debug, Default, Clone)]
+pub struct ChangeSet {
+    pub loaded: HashSet<AssetHandle<DynAsset>>,
+    pub reloaded: HashSet<AssetHandle<DynAsset>>,
+    pub written: HashSet<AssetHandle<DynAsset>>,
+}
+
+impl ChangeSet {
+    pub fn is_empty(&self) -> bool {
+        self.loaded.is_empty() && self.reloaded.is_empty() && self.written.is_empty()
+    }
+}
+
+/// Id of a loaded asset which stays the same across sessions
+///
+/// Derived from the path relative to the root and the name the type is registered under,
+/// so it changes when the file is moved or the type is registered under another name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PersistentId(pub u64);
+
+/// Group of assets that can be unloaded together
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AssetScope(u64);
+
+/// Group of async loads whose progress can be queried together
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BatchHandle(u64);
+
+#[derive(Default)]
+struct ScopeEntry {
+    parent: Option<AssetScope>,
+    handles: Vec<AssetHandle<DynAsset>>,
+}
+
+#[derive(Clone, Copy)]
+struct RegisteredType {
+    load: fn(&mut Assets, &Path, bool) -> AssetHandle<DynAsset>,
+    watch: fn(&mut Assets, AssetHandle<DynAsset>, &Path),
+    write: Option<fn(&mut Assets, AssetHandle<DynAsset>, &Path)>,
+}
 
-    fn convert(source: &Self::SourceAsset, params: &Self::Params) -> Self;
+#[derive(Clone, Copy)]
+struct AppendType {
+    append: fn(&dyn Asset, usize, &mut fs::File),
+    entries: fn(&dyn Asset) -> usize,
+}
+
+#[derive(Clone, Copy)]
+struct SnapshotType {
+    save: fn(&dyn Asset) -> Vec<u8>,
+    restore: fn(&mut Assets, u64, &[u8]) -> Option<AssetHandle<DynAsset>>,
+    reload: fn(&mut Assets, u64, &Path) -> Option<AssetHandle<DynAsset>>,
+}
+
+// params of the latest tracked conversion, converted again when the source reloads
+struct TrackedConvert {
+    params: Box<dyn Any + Send>,
+    reconvert: fn(&mut Assets, AssetHandle<DynAsset>, &(dyn Any + Send)),
 }
 
 pub struct Assets {
-    cache: HashMap<AssetHandle<DynAsset>, DynAsset>,
-    render_cache: HashMap<AssetHandle<DynAsset>, DynRenderAsset>,
+    root: Option<PathBuf>,
+    // namespace of this instance in the shared render cache
+    instance: u64,
+    source: Arc<dyn AssetSource>,
+    preprocessor: Option<PreprocessorFn>,
+    cache: HashMap<AssetHandle<DynAsset>, SharedAsset>,
+    render_cache: HashMap<RenderKey, DynRenderAsset>,
 
     load_handles: HashMap<AssetHandle<DynAsset>, PathBuf>,
     load_dirty: HashSet<AssetHandle<DynAsset>>,
+    frozen: HashSet<AssetHandle<DynAsset>>,
+    write_batch: bool,
 
     // async loading
-    load_sender: mpsc::Sender<(AssetHandle<DynAsset>, DynAsset)>,
-    load_receiver: mpsc::Receiver<(AssetHandle<DynAsset>, DynAsset)>,
+    load_sender: mpsc::Sender<LoadedAsset>,
+    load_receiver: mpsc::Receiver<LoadedAsset>,
+    load_sequence: u64,
+    load_pending: HashSet<AssetHandle<DynAsset>>,
+    load_cancelled: HashSet<AssetHandle<DynAsset>>,
+    load_cancel_flags: HashMap<AssetHandle<DynAsset>, Arc<AtomicBool>>,
+    load_limiter: Arc<LoadLimiter>,
+    load_workers: WorkerPool,
+    load_errors: HashMap<AssetHandle<DynAsset>, AssetLoadError>,
+    load_callbacks: HashMap<AssetHandle<DynAsset>, Vec<DynAssetLoadedFn>>,
+    path_to_handle: HashMap<(PathBuf, TypeId), AssetHandle<DynAsset>>,
+    simulated_delay: Option<Duration>,
+    byte_limiter: Arc<ByteLimiter>,
+
+    // explicit ids
+    explicit_ids: HashSet<u64>,
+    id_collision_policy: IdCollisionPolicy,
+
+    // reference counting of handed out handles
+    handle_refs: HashMap<u64, Weak<HandleRef>>,
+    dropped_sender: mpsc::Sender<AssetHandle<DynAsset>>,
+    dropped_receiver: mpsc::Receiver<AssetHandle<DynAsset>>,
+
+    // ordered delivery
+    ordered_delivery: bool,
+    ordered_next: u64,
+    ordered_buffer: BTreeMap<u64, LoadedAsset>,
+
+    // hash of the file content an asset was loaded from
+    content_hashes: HashMap<AssetHandle<DynAsset>, u64>,
+
+    // file an asset was loaded from and its modification time at load
+    asset_paths: HashMap<AssetHandle<DynAsset>, PathBuf>,
+    load_mtimes: HashMap<AssetHandle<DynAsset>, SystemTime>,
+
+    changes: ChangeSet,
+
+    // placeholders of types which are not loaded yet
+    defaults: HashMap<TypeId, SharedAsset>,
+
+    // copy on write
+    clone_functions: HashMap<TypeId, fn(&dyn Asset) -> SharedAsset>,
+
+    // stats
+    size_functions: HashMap<TypeId, fn(&dyn Asset) -> usize>,
 
     // reloading
-    reload_functions: HashMap<TypeId, DynAssetLoadFn>,
+    reload_functions: HashMap<AssetHandle<DynAsset>, DynAssetLoadFn>,
     reload_handles: HashMap<PathBuf, Vec<AssetHandle<DynAsset>>>,
-    reload_watcher: notify_debouncer_mini::Debouncer<notify_debouncer_mini::notify::FsEventWatcher>,
+    reload_watcher:
+        notify_debouncer_mini::Debouncer<notify_debouncer_mini::notify::RecommendedWatcher>,
     reload_receiver: mpsc::Receiver<PathBuf>,
     reload_sender: mpsc::Sender<PathBuf>,
+    reload_raw_events: Arc<AtomicU64>,
+    reload_stats: WatcherStats,
+    reload_interceptor: Option<ReloadInterceptorFn>,
+    // sidecar file to the asset it configures
+    sidecars: HashMap<PathBuf, PathBuf>,
+    // file which did not exist when watched to the directory watched instead
+    pending_watches: HashMap<PathBuf, PathBuf>,
+    watch_error_sender: mpsc::Sender<WatchError>,
+    watch_error_receiver: mpsc::Receiver<WatchError>,
+    // last seen content of watched files, events which do not change it are ignored
+    watched_hashes: HashMap<PathBuf, u64>,
+    // paths passed to force_reload, reloaded even if unchanged
+    forced_reloads: Mutex<HashSet<PathBuf>>,
+    // path to the assets reloaded when it changes
+    dependents: HashMap<PathBuf, Vec<AssetHandle<DynAsset>>>,
 
     // writing
     write_functions: HashMap<TypeId, DynAssetWriteFn>,
+    remove_functions: HashMap<TypeId, Vec<DynAssetRemoveFn>>,
+    append_types: HashMap<TypeId, AppendType>,
+    append_written: HashMap<AssetHandle<DynAsset>, usize>,
+    write_errors: HashMap<AssetHandle<DynAsset>, std::io::Error>,
+    written_callbacks: HashMap<AssetHandle<DynAsset>, Vec<DynAssetWrittenFn>>,
+
+    // singletons
+    singletons: HashMap<TypeId, AssetHandle<DynAsset>>,
+
+    // names
+    names: HashMap<String, AssetHandle<DynAsset>>,
+    handle_names: HashMap<AssetHandle<DynAsset>, String>,
+    aliases: HashMap<String, AssetHandle<DynAsset>>,
+    handle_aliases: HashMap<AssetHandle<DynAsset>, Vec<String>>,
+
+    // persistent ids of loaded files
+    persistent_ids: HashMap<PersistentId, AssetHandle<DynAsset>>,
+    handle_persistent_ids: HashMap<AssetHandle<DynAsset>, PersistentId>,
+
+    // metadata
+    meta: HashMap<AssetHandle<DynAsset>, HashMap<String, String>>,
+
+    // labeled assets
+    labeled_loaders: HashMap<PathBuf, MultiAssetLoadFn>,
+    labels: HashMap<AssetHandle<DynAsset>, String>,
+
+    // load options
+    default_options: LoadOptions,
+    type_options: HashMap<TypeId, LoadOptions>,
+
+    // manifests
+    registered_types: HashMap<String, RegisteredType>,
+    type_names: HashMap<TypeId, String>,
+
+    // snapshots
+    snapshot_types: HashMap<String, SnapshotType>,
+    snapshot_names: HashMap<TypeId, String>,
+
+    // scopes
+    scopes: HashMap<AssetScope, ScopeEntry>,
+    next_scope: u64,
+
+    // load batches
+    batches: HashMap<BatchHandle, Vec<AssetHandle<DynAsset>>>,
+    next_batch: u64,
+
+    render_generations: HashMap<(AssetHandle<DynAsset>, TypeId), u64>,
+    render_versions: HashMap<RenderKey, (u64, u64)>,
+    // failed conversions are not retried until the source or recipe changes
+    convert_errors: HashMap<RenderKey, ((u64, u64), ConvertError)>,
+    content_renders: Option<HashMap<(u64, TypeId, u64), DynRenderAsset>>,
+    convert_misses: HashMap<(AssetHandle<DynAsset>, TypeId), u64>,
+    convert_hits: HashMap<(AssetHandle<DynAsset>, TypeId), u64>,
+    render_sources: HashMap<u64, RenderKey>,
+    tracked_converts: HashMap<(AssetHandle<DynAsset>, TypeId), TrackedConvert>,
+    shared_render_cache: Option<Arc<Mutex<SharedRenderCache>>>,
+    // least recently used render assets are evicted above the limit
+    render_limit: Option<usize>,
+    render_last_used: HashMap<RenderKey, u64>,
+    render_clock: u64,
+
+    // async converting
+    source_versions: HashMap<AssetHandle<DynAsset>, u64>,
+    convert_pending: HashMap<RenderKey, (u64, u64)>,
+    recipe_versions: HashMap<TypeId, u64>,
+    convert_sender: mpsc::Sender<ConvertedAsset>,
+    convert_receiver: mpsc::Receiver<ConvertedAsset>,
+}
+
+impl Default for Assets {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Assets {
+    /// Create assets loading async on one worker per available core
     pub fn new() -> Self {
+        Self::with_workers(default_workers())
+    }
+
+    /// Create assets loading async on a fixed number of worker threads
+    ///
+    /// The threads are started here rather than on the first load
+    pub fn with_workers(workers: usize) -> Self {
+        Self::with_workers_and_debounce(workers, DEFAULT_DEBOUNCE)
+    }
+
+    /// Create assets reading files through a source, see [`Assets::set_source`]
+    pub fn with_source(source: Box<dyn AssetSource>) -> Self {
+        let mut assets = Self::new();
+        assets.set_source(source);
+        assets
+    }
+
+    /// Create assets which wait for file changes to settle for a duration before reloading
+    ///
+    /// Defaults to 100ms, slow storage may need 500ms or more to not reload partially
+    /// written files
+    pub fn with_debounce(debounce: Duration) -> Self {
+        Self::with_workers_and_debounce(default_workers(), debounce)
+    }
+
+    /// Create assets with both a fixed number of workers and a reload debounce duration
+    pub fn with_workers_and_debounce(workers: usize, debounce: Duration) -> Self {
+        Self::try_with_workers_and_debounce(workers, debounce).expect("could not create watcher")
+    }
+
+    /// Create assets with both a fixed number of workers and a reload debounce duration
+    ///
+    /// Fails if the file watcher can not be created
+    pub fn try_with_workers_and_debounce(
+        workers: usize,
+        debounce: Duration,
+    ) -> Result<Self, WatchError> {
         let (reload_sender, reload_receiver) = mpsc::channel();
+        let (watch_error_sender, watch_error_receiver) = mpsc::channel();
+        let watch_error_sender_copy = watch_error_sender.clone();
         let (loaded_sender, loaded_receiver) = mpsc::channel();
+        let (convert_sender, convert_receiver) = mpsc::channel();
+        let (dropped_sender, dropped_receiver) = mpsc::channel();
         let sender_copy = reload_sender.clone();
+        let reload_raw_events = Arc::new(AtomicU64::new(0));
+        let raw_events_copy = reload_raw_events.clone();
 
         let reload_watcher = notify_debouncer_mini::new_debouncer(
-            Duration::from_millis(100),
+            debounce,
             move |res: notify_debouncer_mini::DebounceEventResult| match res {
                 Ok(events) => {
+                    raw_events_copy.fetch_add(events.len() as u64, SeqCst);
                     for event in events {
-                        sender_copy
-                            .clone()
-                            .send(event.path)
-                            .expect("could not send");
+                        let _ = sender_copy.send(event.path);
                     }
                 }
-                Err(err) => println!("debounced result error: {}", err),
+                Err(err) => {
+                    let _ = watch_error_sender_copy.send(WatchError::Event(err));
+                }
             },
         )
-        .expect("could not create watcher");
+        .map_err(WatchError::Create)?;
 
-        Self {
+        Ok(Self {
+            root: None,
+            instance: NEXT_INSTANCE.fetch_add(1, SeqCst),
+            source: Arc::new(FsSource::new()),
+            preprocessor: None,
             cache: HashMap::new(),
             render_cache: HashMap::new(),
             load_dirty: HashSet::new(),
+            frozen: HashSet::new(),
+            write_batch: false,
             reload_handles: HashMap::new(),
             load_handles: HashMap::new(),
 
             write_functions: HashMap::new(),
+            remove_functions: HashMap::new(),
+            append_types: HashMap::new(),
+            append_written: HashMap::new(),
+            write_errors: HashMap::new(),
+            written_callbacks: HashMap::new(),
 
             reload_functions: HashMap::new(),
             reload_receiver,
             reload_sender,
             reload_watcher,
+            reload_raw_events,
+            reload_stats: WatcherStats::default(),
+            reload_interceptor: None,
+            sidecars: HashMap::new(),
+            pending_watches: HashMap::new(),
+            watch_error_sender,
+            watch_error_receiver,
+            watched_hashes: HashMap::new(),
+            forced_reloads: Mutex::new(HashSet::new()),
+            dependents: HashMap::new(),
 
             load_sender: loaded_sender,
             load_receiver: loaded_receiver,
+            load_sequence: 0,
+            load_pending: HashSet::new(),
+            load_cancelled: HashSet::new(),
+            load_cancel_flags: HashMap::new(),
+            load_limiter: Arc::new(LoadLimiter::new(usize::MAX)),
+            load_workers: WorkerPool::new(workers),
+            load_errors: HashMap::new(),
+            load_callbacks: HashMap::new(),
+            path_to_handle: HashMap::new(),
+            simulated_delay: None,
+            byte_limiter: Arc::new(ByteLimiter::new()),
+
+            explicit_ids: HashSet::new(),
+            id_collision_policy: IdCollisionPolicy::default(),
+
+            handle_refs: HashMap::new(),
+            dropped_sender,
+            dropped_receiver,
+
+            ordered_delivery: false,
+            ordered_next: 0,
+            ordered_buffer: BTreeMap::new(),
+
+            content_hashes: HashMap::new(),
+
+            asset_paths: HashMap::new(),
+            load_mtimes: HashMap::new(),
+
+            changes: ChangeSet::default(),
+
+            defaults: HashMap::new(),
+
+            clone_functions: HashMap::new(),
+
+            size_functions: HashMap::new(),
+
+            singletons: HashMap::new(),
+
+            names: HashMap::new(),
+            handle_names: HashMap::new(),
+            aliases: HashMap::new(),
+            handle_aliases: HashMap::new(),
+
+            meta: HashMap::new(),
+
+            persistent_ids: HashMap::new(),
+            handle_persistent_ids: HashMap::new(),
+
+            labeled_loaders: HashMap::new(),
+            labels: HashMap::new(),
+
+            default_options: LoadOptions::default(),
+            type_options: HashMap::new(),
+
+            registered_types: HashMap::new(),
+            type_names: HashMap::new(),
+
+            snapshot_types: HashMap::new(),
+            snapshot_names: HashMap::new(),
+
+            scopes: HashMap::new(),
+            next_scope: 0,
+
+            batches: HashMap::new(),
+            next_batch: 0,
+
+            render_generations: HashMap::new(),
+            render_versions: HashMap::new(),
+            convert_errors: HashMap::new(),
+            content_renders: None,
+            convert_misses: HashMap::new(),
+            convert_hits: HashMap::new(),
+            render_sources: HashMap::new(),
+            tracked_converts: HashMap::new(),
+            shared_render_cache: None,
+            render_limit: None,
+            render_last_used: HashMap::new(),
+            render_clock: 0,
+
+            source_versions: HashMap::new(),
+            convert_pending: HashMap::new(),
+            recipe_versions: HashMap::new(),
+            convert_sender,
+            convert_receiver,
+        })
+    }
+
+    //
+    // Configuration
+    //
+
+    /// Read files through a source instead of the file system
+    ///
+    /// Applies to loads and reloads started afterwards. Loaders read through the source
+    /// with [`read_file`], [`Assets::load_source`] creates assets from the bytes it reads.
+    /// Paths the source has no [`AssetSource::local_path`] for are used as given and
+    /// watched through [`AssetSource::watch`]. Writes still go to the file system
+    pub fn set_source(&mut self, source: Box<dyn AssetSource>) {
+        self.source = Arc::from(source);
+    }
+
+    /// Transform the bytes of a file before they are parsed, e.g. to expand shader includes
+    ///
+    /// Applies to loads and reloads started afterwards, to the bytes of every file read
+    /// through [`read_file`] and [`Assets::load_source`]
+    pub fn set_preprocessor(
+        &mut self,
+        preprocessor: impl Fn(&Path, Vec<u8>) -> Vec<u8> + Send + Sync + 'static,
+    ) {
+        self.preprocessor = Some(Arc::new(preprocessor));
+    }
+
+    /// Share converted render assets with other instances
+    ///
+    /// Converting a source loaded from the same path in any of the instances makes the
+    /// render asset available to all of them
+    pub fn set_shared_render_cache(&mut self, cache: Arc<Mutex<SharedRenderCache>>) {
+        self.shared_render_cache = Some(cache);
+    }
+
+    /// Keep at most `limit` render assets
+    ///
+    /// The least recently converted render asset is evicted first and converted again
+    /// from its source when needed. Evicted render assets stay alive as long as clones
+    /// of their [`ArcHandle`] exist, they are only dropped from the render cache
+    pub fn set_render_cache_limit(&mut self, limit: usize) {
+        self.render_limit = Some(limit.max(1));
+        self.evict_renders();
+    }
+
+    //
+    // Root
+    //
+
+    /// Resolve relative paths against a root directory instead of the working directory
+    pub fn set_root(&mut self, root: &Path) {
+        self.root = Some(root.to_path_buf());
+    }
+
+    /// Resolve relative paths against the directory of the executable
+    ///
+    /// Falls back to the working directory and returns the error if the executable path
+    /// is unknown
+    pub fn set_root_to_exe_dir(&mut self) -> std::io::Result<()> {
+        match std::env::current_exe() {
+            Ok(exe) => {
+                self.root = exe.parent().map(Path::to_path_buf);
+                Ok(())
+            }
+            Err(err) => {
+                self.root = None;
+                Err(err)
+            }
+        }
+    }
+
+    // relative paths are joined onto the root, absolute paths are kept
+    fn resolve(&self, path: &Path) -> PathBuf {
+        match &self.root {
+            Some(root) => root.join(path),
+            None => path.to_path_buf(),
+        }
+    }
+
+    // canonicalize a resolved path, files which do not exist yet through their directory
+    //
+    // paths of sources outside the file system are kept as resolved
+    fn canonical(&self, path: &Path) -> PathBuf {
+        let path = self.resolve(path);
+        if self.source.local_path(&path).is_none() {
+            return path;
         }
+        fs::canonicalize(&path)
+            .or_else(|_| {
+                let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+                let name = path.file_name().ok_or(std::io::ErrorKind::NotFound)?;
+                fs::canonicalize(dir.unwrap_or(Path::new("."))).map(|dir| dir.join(name))
+            })
+            .or_else(|_| std::path::absolute(&path))
+            .unwrap_or(path)
     }
 
     //
@@ -101,16 +868,50 @@ impl Assets {
     //
 
     pub fn insert<T: Asset + 'static>(&mut self, data: T) -> AssetHandle<T> {
-        let handle = AssetHandle::<T>::new();
+        let handle = self.track(AssetHandle::<T>::new());
         self.cache
-            .insert(handle.clone().clone_typed::<DynAsset>(), Box::new(data));
+            .insert(handle.clone().clone_typed::<DynAsset>(), Arc::new(data));
         handle
     }
 
-    // TODO: add get_or_default (e.g. 1x1 white pixel for image)
-    //
-    // could return error union [Ok, Invalid, Loading]
-    pub fn get<T: Asset + 'static>(&mut self, handle: AssetHandle<T>) -> Option<&T> {
+    /// Insert an asset under an explicit id, e.g. when restoring serialized handles
+    ///
+    /// Pending loads with the same id are resolved by [`Assets::set_id_collision_policy`]
+    pub fn insert_with_id<T: Asset>(&mut self, id: u64, data: T) -> AssetHandle<T> {
+        let handle = self.track(AssetHandle::<T>::with_id(id));
+        self.explicit_ids.insert(id);
+        self.cache
+            .insert(handle.clone_typed::<DynAsset>(), Arc::new(data));
+        handle
+    }
+
+    /// Insert an asset parsed from bytes, e.g. embedded with `include_bytes!`
+    ///
+    /// The asset has no file, so it is never reloaded. If parsing fails the asset is
+    /// missing and the error is available through [`Assets::load_error`]
+    pub fn insert_from_bytes<T: LoadableFromBytes>(&mut self, bytes: &[u8]) -> AssetHandle<T> {
+        match T::from_bytes(bytes) {
+            Ok(data) => self.insert(data),
+            Err(err) => {
+                let handle = self.track(AssetHandle::<T>::new());
+                self.load_errors
+                    .insert(handle.clone_typed::<DynAsset>(), err);
+                handle
+            }
+        }
+    }
+
+    /// Set how loads colliding with explicitly inserted ids are handled
+    pub fn set_id_collision_policy(&mut self, policy: IdCollisionPolicy) {
+        self.id_collision_policy = policy;
+    }
+
+    pub fn get<T: Asset + 'static>(&self, handle: AssetHandle<T>) -> Option<&T> {
+        debug_assert!(
+            handle.ty_id == TypeId::of::<T>(),
+            "handle does not point to a {}",
+            std::any::type_name::<T>()
+        );
         self.cache
             .get(&handle.clone_typed::<DynAsset>())
             .map(|asset| {
@@ -121,57 +922,533 @@ impl Assets {
             })
     }
 
-    pub fn get_mut<T: Asset + 'static>(&mut self, handle: AssetHandle<T>) -> Option<&mut T> {
-        // invalidate gpu cache
-        self.render_cache
-            .remove(&handle.clone().clone_typed::<DynAsset>());
+    /// Get an asset through a weak handle, None if the asset was removed
+    pub fn get_weak<T: Asset>(&self, handle: WeakAssetHandle<T>) -> Option<&T> {
+        self.get(handle.clone_strong())
+    }
 
-        // set dirty
-        self.load_dirty
-            .insert(handle.clone().clone_typed::<DynAsset>());
+    /// Get an asset, or the placeholder of its type if it is not loaded
+    ///
+    /// The placeholder is created once per type and shared by all handles
+    pub fn get_or_default<T: Asset + DefaultAsset>(&mut self, handle: AssetHandle<T>) -> &T {
+        let erased = handle.clone_typed::<DynAsset>();
+        let asset = if self.cache.contains_key(&erased) {
+            &self.cache[&erased]
+        } else {
+            self.defaults
+                .entry(TypeId::of::<T>())
+                .or_insert_with(|| Arc::new(T::default_asset()))
+        };
+        asset
+            .as_any()
+            .downcast_ref::<T>()
+            .expect("could not downcast")
+    }
 
-        // get value and convert to T
-        self.cache
-            .get_mut(&handle.clone_typed::<DynAsset>())
-            .map(|asset| {
-                asset
-                    .as_any_mut()
-                    .downcast_mut::<T>()
-                    .expect("could not downcast")
+    /// Get several assets in one call, in the same order as the handles
+    ///
+    /// Only borrows self immutably, so the references can be held together. The handles are
+    /// looked up by id without being cloned, which makes this cheaper than calling get per handle
+    pub fn get_many<T: Asset>(&self, handles: &[AssetHandle<T>]) -> Vec<Option<&T>> {
+        handles
+            .iter()
+            .map(|handle| {
+                self.cache.get(handle as &dyn HandleKey).map(|asset| {
+                    asset
+                        .as_any()
+                        .downcast_ref::<T>()
+                        .expect("could not downcast")
+                })
             })
+            .collect()
     }
 
-    //
-    // Reloading
-    //
-
-    // TODO: investigate using watch and write manually main, maybe store path in asset handle also
+    /// Iterate over all loaded assets of a type with their handles
+    pub fn iter<T: Asset>(&self) -> impl Iterator<Item = (AssetHandle<T>, &T)> + '_ {
+        self.cache
+            .iter()
+            .filter(|(handle, _)| handle.ty_id == TypeId::of::<T>())
+            .map(|(handle, asset)| {
+                let asset = asset
+                    .as_any()
+                    .downcast_ref::<T>()
+                    .expect("could not downcast");
+                (self.tracked(handle.clone_typed::<T>()), asset)
+            })
+    }
 
-    /// Load a file
-    pub fn load<T: Asset + LoadableAsset>(&mut self, path: &Path, sync: bool) -> AssetHandle<T> {
-        let path = fs::canonicalize(path).unwrap();
-        let handle = AssetHandle::<T>::new();
+    /// Get a shared pointer to an asset which can be held beyond the borrow of Assets
+    ///
+    /// While the pointer is alive the asset counts as shared, so [`Assets::get_mut`]
+    /// copies it first if its type is registered for copy on write and returns None
+    /// otherwise. The pointer keeps the old value in both cases.
+    /// Pending writes of the asset are written from a copy the same way. Without copy on
+    /// write they stay pending, reported by [`Assets::write_error`], until the pointer is dropped
+    pub fn get_arc<T: Asset>(&self, handle: &AssetHandle<T>) -> Option<Arc<T>> {
+        let asset: Arc<dyn Any + Send + Sync> =
+            self.cache.get(&handle.clone_typed::<DynAsset>())?.clone();
+        Some(asset.downcast::<T>().expect("could not downcast"))
+    }
 
-        if sync {
-            let data = T::load(&path);
-            self.cache
-                .insert(handle.clone().clone_typed::<DynAsset>(), Box::new(data));
-        } else {
-            let path_clone = path.clone();
-            let handle_clone = handle.clone();
-            let loaded_sender_clone = self.load_sender.clone();
-            std::thread::spawn(move || {
-                std::thread::sleep(Duration::from_millis(20000));
-                let data = T::load(&path_clone);
-                loaded_sender_clone
-                    .send((handle_clone.clone_typed::<DynAsset>(), Box::new(data)))
-                    .expect("could not send");
+    /// Get an asset, or why it is not available
+    ///
+    /// Unlike get this tells a load in flight apart from a failed load
+    pub fn try_get<T: Asset>(&self, handle: AssetHandle<T>) -> Result<&T, GetError<'_>> {
+        // handles retyped to the wrong type would otherwise panic in the downcast
+        if handle.ty_id != TypeId::of::<T>() {
+            return Err(GetError::TypeMismatch {
+                expected: std::any::type_name::<T>(),
             });
         }
 
-        handle
-    }
-
+        let erased = handle.clone_typed::<DynAsset>();
+        if let Some(asset) = self.cache.get(&erased) {
+            return Ok(asset
+                .as_any()
+                .downcast_ref::<T>()
+                .expect("could not downcast"));
+        }
+        if self.load_pending.contains(&erased) {
+            return Err(GetError::Loading);
+        }
+        match self.load_errors.get(&erased) {
+            Some(err) => Err(GetError::Failed(err)),
+            None => Err(GetError::Missing),
+        }
+    }
+
+    /// Get whether an asset is loading, loaded or failed to load
+    pub fn asset_state<T: Asset>(&self, handle: AssetHandle<T>) -> AssetState {
+        let erased = handle.clone_typed::<DynAsset>();
+        if self.load_pending.contains(&erased) {
+            AssetState::Loading
+        } else if self.cache.contains_key(&erased) {
+            AssetState::Loaded
+        } else {
+            match self.load_errors.get(&erased) {
+                Some(AssetLoadError::NotFound(_)) | None => AssetState::NotFound,
+                Some(_) => AssetState::Failed,
+            }
+        }
+    }
+
+    /// Check if a handle points to a cached asset
+    pub fn contains<T: Asset>(&self, handle: AssetHandle<T>) -> bool {
+        self.cache.contains_key(&handle.clone_typed::<DynAsset>())
+    }
+
+    /// Check if an async load of a handle has not been delivered by poll_loaded yet
+    pub fn is_pending<T: Asset>(&self, handle: AssetHandle<T>) -> bool {
+        self.load_pending
+            .contains(&handle.clone_typed::<DynAsset>())
+    }
+
+    /// Get why the latest load or reload of an asset failed
+    ///
+    /// A failed load leaves the asset missing, a failed reload keeps the previous version
+    pub fn load_error<T: Asset>(&self, handle: &AssetHandle<T>) -> Option<&AssetLoadError> {
+        self.load_errors.get(&handle.clone_typed::<DynAsset>())
+    }
+
+    /// Get why the latest write of an asset failed, cleared by the next successful write
+    pub fn write_error<T: Asset>(&self, handle: &AssetHandle<T>) -> Option<&std::io::Error> {
+        self.write_errors.get(&handle.clone_typed::<DynAsset>())
+    }
+
+    /// Run a callback once the next write of an asset by poll_write is done
+    ///
+    /// The callback runs once with the result of that write
+    pub fn on_written<T: Asset>(
+        &mut self,
+        handle: &AssetHandle<T>,
+        f: impl FnOnce(Result<(), std::io::Error>) + Send + 'static,
+    ) {
+        self.written_callbacks
+            .entry(handle.clone_typed::<DynAsset>())
+            .or_default()
+            .push(Box::new(f));
+    }
+
+    // run the callbacks waiting for a write of the handle
+    fn finish_write(&mut self, handle: &AssetHandle<DynAsset>) {
+        let error = self.write_errors.get(handle);
+        for callback in self.written_callbacks.remove(handle).unwrap_or_default() {
+            callback(match error {
+                Some(err) => Err(std::io::Error::new(err.kind(), err.to_string())),
+                None => Ok(()),
+            });
+        }
+    }
+
+    /// Get mutable access to an asset
+    ///
+    /// Assets shared with other handles are copied first so the other handles keep the
+    /// old value. Returns None for shared assets of types without copy on write,
+    /// see [`Assets::register_copy_on_write`]
+    pub fn get_mut<T: Asset + 'static>(&mut self, handle: AssetHandle<T>) -> Option<&mut T> {
+        let erased = handle.clone_typed::<DynAsset>();
+
+        // frozen assets are read only
+        if self.frozen.contains(&erased) {
+            return None;
+        }
+
+        // copy on write so the other handles are not affected
+        if !self.unshare(&erased) {
+            return None;
+        }
+
+        // invalidate gpu cache
+        self.invalidate_render(&erased);
+        self.invalidate_shared_render(&erased);
+
+        // set dirty
+        self.load_dirty.insert(erased.clone());
+
+        // content no longer matches the file
+        self.content_hashes.remove(&erased);
+
+        // get value and convert to T
+        self.cache
+            .get_mut(&erased)
+            .and_then(Arc::get_mut)
+            .map(|asset| {
+                asset
+                    .as_any_mut()
+                    .downcast_mut::<T>()
+                    .expect("could not downcast")
+            })
+    }
+
+    // make a cached asset exclusively owned, copying it first if it is shared and its type
+    // is registered for copy on write. False if it is not loaded or stays shared
+    fn unshare(&mut self, handle: &AssetHandle<DynAsset>) -> bool {
+        let Some(asset) = self.cache.get_mut(handle) else {
+            return false;
+        };
+        if Arc::get_mut(asset).is_some() {
+            return true;
+        }
+        match self.clone_functions.get(&handle.ty_id) {
+            Some(clone_fn) => {
+                *asset = clone_fn(asset.as_ref());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replace the asset behind a handle and return the previous value
+    ///
+    /// The handle stays valid, render assets are converted again and the asset is
+    /// written like after get_mut. An async load still in flight is cancelled so it does
+    /// not overwrite the new value. The previous value is None if there was none, or if
+    /// it is still shared and its type is not registered for copy on write
+    ///
+    /// Fails for frozen assets
+    pub fn replace<T: Asset>(
+        &mut self,
+        handle: AssetHandle<T>,
+        data: T,
+    ) -> Result<Option<T>, AssetError> {
+        let erased = handle.clone_typed::<DynAsset>();
+        if self.frozen.contains(&erased) {
+            return Err(AssetError::Frozen);
+        }
+
+        self.cancel_load(handle);
+        self.load_errors.remove(&erased);
+        let previous = self.cache.insert(erased.clone(), Arc::new(data));
+
+        self.invalidate_render(&erased);
+        self.invalidate_shared_render(&erased);
+        self.load_dirty.insert(erased.clone());
+        self.content_hashes.remove(&erased);
+
+        Ok(previous.and_then(|previous| self.take_owned(previous)))
+    }
+
+    // take a removed asset out of its arc, copying it if it is still shared
+    fn take_owned<T: Asset>(&self, mut asset: SharedAsset) -> Option<T> {
+        if Arc::get_mut(&mut asset).is_none() {
+            let clone_fn = self.clone_functions.get(&TypeId::of::<T>())?;
+            asset = clone_fn(asset.as_ref());
+        }
+
+        let asset: Arc<dyn Any + Send + Sync> = asset;
+        let asset = asset.downcast::<T>().expect("could not downcast");
+        Arc::try_unwrap(asset).ok()
+    }
+
+    /// Forbid mutating an asset, get_mut returns None until unfrozen
+    ///
+    /// Writes already scheduled for the asset are dropped
+    pub fn freeze<T: Asset + 'static>(&mut self, handle: &AssetHandle<T>) {
+        let erased = handle.clone_typed::<DynAsset>();
+        self.load_dirty.remove(&erased);
+        self.frozen.insert(erased);
+    }
+
+    /// Allow mutating a frozen asset again
+    pub fn unfreeze<T: Asset + 'static>(&mut self, handle: &AssetHandle<T>) {
+        self.frozen.remove(&handle.clone_typed::<DynAsset>());
+    }
+
+    pub fn is_frozen<T: Asset + 'static>(&self, handle: &AssetHandle<T>) -> bool {
+        self.frozen.contains(&handle.clone_typed::<DynAsset>())
+    }
+
+    /// Allow shared assets of a type to be mutated by copying them on write
+    pub fn register_copy_on_write<T: Asset + Clone>(&mut self) {
+        self.clone_functions
+            .insert(TypeId::of::<T>(), Self::clone_erased::<T>);
+    }
+
+    /// Create a new handle sharing the asset of another handle
+    ///
+    /// Mutating either handle copies the asset first, so the other keeps its value
+    pub fn insert_shared<T: Asset + Clone>(
+        &mut self,
+        handle: &AssetHandle<T>,
+    ) -> Option<AssetHandle<T>> {
+        let asset = self.cache.get(&handle.clone_typed::<DynAsset>())?.clone();
+        self.register_copy_on_write::<T>();
+
+        let shared = self.track(AssetHandle::<T>::new());
+        self.cache.insert(shared.clone_typed::<DynAsset>(), asset);
+        Some(shared)
+    }
+
+    fn clone_erased<T: Asset + Clone>(asset: &dyn Asset) -> SharedAsset {
+        Arc::new(
+            asset
+                .as_any()
+                .downcast_ref::<T>()
+                .expect("could not downcast")
+                .clone(),
+        )
+    }
+
+    /// Make assets with identical content share storage
+    ///
+    /// Compares the content of the files assets were loaded from, assets that were
+    /// inserted or modified since loading are skipped. Merged assets are only mutable
+    /// through [`Assets::get_mut`] if their type is registered for copy on write, the same
+    /// applies to writing them. Returns how many assets were merged
+    pub fn dedup(&mut self) -> usize {
+        let mut canonical: HashMap<(TypeId, u64), SharedAsset> = HashMap::new();
+        let mut merged = 0;
+        for (handle, hash) in self.content_hashes.iter() {
+            let Some(asset) = self.cache.get_mut(handle) else {
+                continue;
+            };
+
+            match canonical.get(&(handle.ty_id, *hash)) {
+                Some(shared) if !Arc::ptr_eq(shared, asset) => {
+                    *asset = shared.clone();
+                    merged += 1;
+                }
+                Some(_) => {}
+                None => {
+                    canonical.insert((handle.ty_id, *hash), asset.clone());
+                }
+            }
+        }
+        merged
+    }
+
+    //
+    // Reloading
+    //
+
+    // TODO: investigate using watch and write manually main, maybe store path in asset handle also
+
+    /// Load a file
+    ///
+    /// Loading a file which is already loaded as the same type returns the existing handle
+    pub fn load<T: Asset + LoadableAsset>(&mut self, path: &Path, sync: bool) -> AssetHandle<T> {
+        self.load_using(path, sync, T::load)
+    }
+
+    fn load_using<T: Asset>(
+        &mut self,
+        path: &Path,
+        sync: bool,
+        loader: fn(&Path) -> Result<T, AssetLoadError>,
+    ) -> AssetHandle<T> {
+        let resolved = self.resolve(path);
+        let Ok(path) = self.load_path(&resolved) else {
+            let handle = self.track(AssetHandle::<T>::new().with_path(&self.canonical(path)));
+            self.load_errors.insert(
+                handle.clone_typed::<DynAsset>(),
+                AssetLoadError::NotFound(resolved),
+            );
+            return handle;
+        };
+
+        // loading the same file as the same type again shares the handle
+        let key = (path.clone(), TypeId::of::<T>());
+        if let Some(existing) = self.path_to_handle.get(&key)
+            && (self.cache.contains_key(existing) || self.load_pending.contains(existing))
+        {
+            let existing = existing.clone_typed();
+            return self.track(existing);
+        }
+
+        let handle = self.track(AssetHandle::<T>::new().with_path(&path));
+        let erased = handle.clone_typed::<DynAsset>();
+        self.path_to_handle.insert(key, erased.clone());
+        self.asset_paths.insert(erased.clone(), path.clone());
+        self.register_persistent::<T>(&erased, &path);
+
+        if sync {
+            let data = match with_context(&self.load_context(), || loader(&path)) {
+                Ok(data) => data,
+                Err(err) => {
+                    self.load_errors.insert(erased, err.at_path(&path));
+                    return handle;
+                }
+            };
+            self.insert_loaded_from(erased, &path, Arc::new(data));
+        } else {
+            let sequence = Some(self.load_sequence);
+            self.load_sequence += 1;
+            self.load_pending.insert(erased.clone());
+
+            let job = self.load_job(sequence, erased, path, loader);
+            let loaded_sender_clone = self.load_sender.clone();
+            self.load_workers
+                .spawn(move || loaded_sender_clone.send(job()).expect("could not send"));
+        }
+
+        handle
+    }
+
+    // load a file on a worker once the load limits allow it
+    fn load_job<T: Asset>(
+        &mut self,
+        sequence: Option<u64>,
+        handle: AssetHandle<DynAsset>,
+        path: PathBuf,
+        loader: fn(&Path) -> Result<T, AssetLoadError>,
+    ) -> impl FnOnce() -> LoadedAsset + Send + 'static {
+        let limiter = self.load_limiter.clone();
+        let byte_limiter = self.byte_limiter.clone();
+        let context = self.load_context();
+        let simulated_delay = self.simulated_delay;
+        let cancelled = self.cancel_flag(&handle);
+        move || {
+            if cancelled.load(SeqCst) {
+                return LoadedAsset::cancelled(sequence, handle);
+            }
+
+            // estimate the loaded size by the file size
+            let size = fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+            let bytes = byte_limiter.acquire(size);
+            let _permit = limiter.acquire();
+            if let Some(delay) = simulated_delay {
+                std::thread::sleep(delay);
+            }
+            let modified = modified_time(&path);
+            let data = catch_load(|| with_context(&context, || loader(&path)));
+            LoadedAsset {
+                sequence,
+                handle,
+                asset: data.map(|data| Box::new(data) as DynAsset),
+                content_hash: content_hash(&path),
+                modified,
+                bytes: Some(bytes),
+            }
+        }
+    }
+
+    // how loaders started now read their files
+    fn load_context(&self) -> LoadContext {
+        LoadContext {
+            source: self.source.clone(),
+            preprocessor: self.preprocessor.clone(),
+        }
+    }
+
+    // canonical path of a file to load, sources outside the file system report
+    // missing files when reading
+    fn load_path(&self, resolved: &Path) -> std::io::Result<PathBuf> {
+        match self.source.local_path(resolved) {
+            Some(_) => fs::canonicalize(resolved),
+            None => Ok(resolved.to_path_buf()),
+        }
+    }
+
+    // insert an asset loaded from a file on the calling thread
+    fn insert_loaded_from(
+        &mut self,
+        erased: AssetHandle<DynAsset>,
+        path: &Path,
+        asset: SharedAsset,
+    ) {
+        if let Some(hash) = content_hash(path) {
+            self.content_hashes.insert(erased.clone(), hash);
+        }
+        if let Some(modified) = modified_time(path) {
+            self.load_mtimes.insert(erased.clone(), modified);
+        }
+        self.changes.loaded.insert(erased.clone());
+        self.cache.insert(erased, asset);
+    }
+
+    /// Load a file on a worker and wait for it without polling
+    ///
+    /// The asset is inserted once the future completes, poll_loaded is not involved.
+    /// The load counts towards the load limits and is pending like one started by
+    /// [`Assets::load`], so loads of the same file share its handle. If the file is
+    /// already loading as the same type, the pending handle is returned. Dropping the
+    /// future leaves the asset to poll_loaded. Works with any async runtime
+    #[cfg(feature = "async")]
+    pub async fn load_async_await<T: Asset + LoadableAsset>(
+        &mut self,
+        path: &Path,
+    ) -> Result<AssetHandle<T>, AssetLoadError> {
+        let resolved = self.resolve(path);
+        let path = self
+            .load_path(&resolved)
+            .map_err(|_| AssetLoadError::NotFound(resolved))?;
+
+        // loading the same file as the same type again shares the handle
+        let key = (path.clone(), TypeId::of::<T>());
+        if let Some(existing) = self.path_to_handle.get(&key)
+            && (self.cache.contains_key(existing) || self.load_pending.contains(existing))
+        {
+            let existing = existing.clone_typed();
+            return Ok(self.track(existing));
+        }
+
+        let handle = self.track(AssetHandle::<T>::new().with_path(&path));
+        let erased = handle.clone_typed::<DynAsset>();
+        self.path_to_handle.insert(key, erased.clone());
+        self.asset_paths.insert(erased.clone(), path.clone());
+        self.register_persistent::<T>(&erased, &path);
+        self.load_pending.insert(erased.clone());
+
+        let job = self.load_job(None, erased.clone(), path, T::load);
+        let (sender, receiver) = oneshot::channel();
+        let loaded_sender = self.load_sender.clone();
+        let receiver = receiver
+            .with_fallback(move |loaded| loaded_sender.send(loaded).expect("could not send"));
+        self.load_workers.spawn(move || sender.send(job()));
+        let loaded = receiver
+            .await
+            .unwrap_or_else(|| LoadedAsset::cancelled(None, erased.clone()));
+
+        self.insert_loaded(loaded);
+        if self.cache.contains_key(&erased) {
+            return Ok(handle);
+        }
+        Err(self
+            .load_errors
+            .remove(&erased)
+            .unwrap_or(AssetLoadError::Cancelled))
+    }
+
     /// Load a file
     ///
     /// Register asset for being watched for hot reloads
@@ -212,234 +1489,5043 @@ impl Assets {
         handle
     }
 
-    /// Register asset for being watched for hot reloads
-    pub fn watch<T: Asset + LoadableAsset>(&mut self, handle: AssetHandle<T>, path: &Path) {
-        let path = fs::canonicalize(path).unwrap();
-
-        // start watching path
-        self.reload_watcher
-            .watcher()
-            .watch(
-                &path,
-                notify_debouncer_mini::notify::RecursiveMode::Recursive,
-            )
-            .unwrap();
+    /// Load a directory as a single asset
+    ///
+    /// `T::load` receives the directory path. The directory is watched so changing any
+    /// file inside it reloads the asset
+    pub fn load_dir_as<T: Asset + LoadableAsset>(
+        &mut self,
+        dir: &Path,
+        sync: bool,
+    ) -> AssetHandle<T> {
+        self.load_watch(dir, sync)
+    }
 
-        // map path to handle
-        let handles = self.reload_handles.entry(path).or_default();
-        handles.push(handle.clone_typed::<DynAsset>());
+    /// Load every file matching a pattern, e.g. `shaders/**/*.wgsl`
+    ///
+    /// `*` and `?` match within a name and `**` matches any number of directories.
+    /// Files failing to load are reported by [`Assets::load_error`] instead of aborting.
+    /// Directories which can not be read do not stop the other files from loading, they are
+    /// collected in [`AssetError::Glob`] together with the handles that did load.
+    /// Files created later are not picked up
+    pub fn load_glob<T: Asset + LoadableAsset + WriteableAsset>(
+        &mut self,
+        pattern: &str,
+        options: LoadOptions,
+    ) -> Result<Vec<AssetHandle<T>>, AssetError> {
+        let root = self.root.clone().unwrap_or_else(|| PathBuf::from("."));
+        let found = glob::find(&root, pattern)?;
+        let handles: Vec<AssetHandle<T>> = found
+            .files
+            .iter()
+            .map(|file| {
+                let path = file.strip_prefix(&root).unwrap_or(file);
+                self.load_with_options(path, options)
+            })
+            .collect();
 
-        // store reload function
-        self.reload_functions
-            .entry(TypeId::of::<T>())
-            .or_insert_with(|| Box::new(|path| Box::new(T::load(path))));
+        if found.errors.is_empty() {
+            Ok(handles)
+        } else {
+            Err(AssetError::Glob {
+                handles: handles.iter().map(AssetHandle::erase).collect(),
+                errors: found.errors,
+            })
+        }
     }
 
-    /// Register asset for being written to disk when updated
-    pub fn write<T: Asset + WriteableAsset>(&mut self, handle: AssetHandle<T>, path: &Path) {
-        let path = fs::canonicalize(path).unwrap();
-        // map handle to path
-        self.load_handles
-            .insert(handle.clone_typed::<DynAsset>(), path.clone());
-
-        // store reload function
-        self.write_functions
-            .entry(TypeId::of::<T>())
-            .or_insert_with(|| {
-                Box::new(|asset, path| {
-                    let typed = asset
-                        .as_any_mut()
-                        .downcast_mut::<T>()
-                        .expect("could not cast during write");
-                    typed.write(path);
-                })
-            });
+    /// Load a file through the source of Assets, see [`Assets::set_source`]
+    ///
+    /// The asset is created from the bytes the source reads.
+    /// Loading a file which is already loaded as the same type returns the existing handle
+    pub fn load_source<T: LoadableFromBytes>(&mut self, path: &Path, sync: bool) -> AssetHandle<T> {
+        let loader = self.source_loader::<T>();
+        self.load_source_using(path, sync, loader)
     }
 
-    //
-    // Render assets
-    //
+    /// Load a file through the source of Assets, reading only the byte ranges the asset asks for
+    ///
+    /// Fails with an io error of kind Unsupported if the source can not read ranges
+    pub fn load_ranged<T: RangedLoadableAsset>(
+        &mut self,
+        path: &Path,
+        sync: bool,
+    ) -> AssetHandle<T> {
+        let source = self.source.clone();
+        let loader: DynAssetLoadFn = Box::new(move |path| {
+            let source = source.as_range_readable().ok_or_else(|| {
+                AssetLoadError::Io(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "source can not read ranges",
+                ))
+            })?;
+            T::load_ranged(path, source).map(|asset| Box::new(asset) as DynAsset)
+        });
+        self.load_source_using(path, sync, loader)
+    }
 
-    pub fn convert<G: ConvertableRenderAsset>(
+    fn load_source_using<T: Asset>(
         &mut self,
-        handle: AssetHandle<G::SourceAsset>,
-        params: &G::Params,
-    ) -> Option<ArcHandle<G>> {
-        // create new if not in cache
-        if !self
-            .render_cache
-            .contains_key(&handle.clone().clone_typed::<DynAsset>())
+        path: &Path,
+        sync: bool,
+        loader: DynAssetLoadFn,
+    ) -> AssetHandle<T> {
+        let path = self.canonical(path);
+
+        // loading the same file as the same type again shares the handle
+        let key = (path.clone(), TypeId::of::<T>());
+        if let Some(existing) = self.path_to_handle.get(&key)
+            && (self.cache.contains_key(existing) || self.load_pending.contains(existing))
         {
-            let asset = self.get(handle.clone());
+            let existing = existing.clone_typed();
+            return self.track(existing);
+        }
 
-            if let Some(asset) = asset {
-                let converted = G::convert(asset, params);
-                self.render_cache.insert(
-                    handle.clone().clone_typed::<DynAsset>(),
-                    ArcHandle::new(converted).upcast(),
-                );
+        let handle = self.track(AssetHandle::<T>::new().with_path(&path));
+        let erased = handle.clone_typed::<DynAsset>();
+        self.path_to_handle.insert(key, erased.clone());
+        self.asset_paths.insert(erased.clone(), path.clone());
+        self.register_persistent::<T>(&erased, &path);
+
+        if sync {
+            match loader(&path) {
+                Ok(asset) => {
+                    self.changes.loaded.insert(erased.clone());
+                    self.cache.insert(erased, Arc::from(asset));
+                }
+                Err(err) => {
+                    self.load_errors.insert(erased, err.at_path(&path));
+                }
             }
+        } else {
+            let sequence = Some(self.load_sequence);
+            self.load_sequence += 1;
+            self.load_pending.insert(erased.clone());
+
+            let loaded_sender_clone = self.load_sender.clone();
+            let cancelled = self.cancel_flag(&erased);
+            self.load_workers.spawn(move || {
+                if cancelled.load(SeqCst) {
+                    loaded_sender_clone
+                        .send(LoadedAsset::cancelled(sequence, erased))
+                        .expect("could not send");
+                    return;
+                }
+                loaded_sender_clone
+                    .send(LoadedAsset {
+                        sequence,
+                        handle: erased,
+                        asset: catch_load(|| loader(&path)),
+                        content_hash: None,
+                        modified: None,
+                        bytes: None,
+                    })
+                    .expect("could not send");
+            });
         }
 
-        // get value and convert to G
-        self.render_cache
-            .get(&handle.clone_typed::<DynAsset>())
-            .map(|a| a.downcast::<G>())
+        handle
     }
 
-    //
-    // Polling
-    //
+    /// Load a file through the source of Assets
+    ///
+    /// Register asset for being reloaded when the source reports a change
+    pub fn load_source_watch<T: LoadableFromBytes>(
+        &mut self,
+        path: &Path,
+        sync: bool,
+    ) -> AssetHandle<T> {
+        let handle = self.load_source(path, sync);
+        self.watch_source(handle.clone(), path);
+        handle
+    }
 
-    // check if any files completed loading and update cache and invalidate render cache
-    pub fn poll_loaded(&mut self) {
-        for (handle, asset) in self.load_receiver.try_iter() {
-            self.cache.insert(handle.clone(), asset);
-            self.render_cache.remove(&handle);
+    /// Register asset for being reloaded through the source when the source reports a change
+    pub fn watch_source<T: LoadableFromBytes>(&mut self, handle: AssetHandle<T>, path: &Path) {
+        let erased = handle.clone_typed::<DynAsset>();
+        if self.watch_path(erased.clone(), path, None) {
+            self.reload_functions
+                .insert(erased, self.source_loader::<T>());
         }
     }
 
-    // check if any files are scheduled for writing to disk
-    pub fn poll_write(&mut self) {
-        for handle in self.load_dirty.drain() {
-            if let Some(path) = self.load_handles.get(&handle) {
-                let asset = self.cache.get_mut(&handle);
-
-                // write if loaded
-                if let Some(asset) = asset {
-                    let write_fn = self
-                        .write_functions
-                        .get(&handle.ty_id)
-                        .expect("could not get write fn");
-
-                    write_fn(asset, path);
-                }
+    fn source_loader<T: LoadableFromBytes>(&self) -> DynAssetLoadFn {
+        let source = self.source.clone();
+        let preprocessor = self.preprocessor.clone();
+        Box::new(move |path| {
+            let mut bytes = source.read(path)?;
+            if let Some(preprocessor) = &preprocessor {
+                bytes = preprocessor(path, bytes);
             }
-        }
+            T::from_bytes(&bytes).map(|asset| Box::new(asset) as DynAsset)
+        })
     }
 
-    // checks if any files changed and spawns a thread which reloads the data
-    pub fn poll_reload(&mut self) {
-        for path in self.reload_receiver.try_iter() {
-            if let Some(handles) = self.reload_handles.get_mut(&path) {
-                for handle in handles {
-                    println!("reload {:?}", path);
+    /// Load every file in a directory the filter accepts, e.g. by extension
+    ///
+    /// Subdirectories are skipped and the handles are sorted by path. Files are loaded in
+    /// the background, failures are reported by [`Assets::load_error`].
+    /// Fails if the directory can not be read
+    pub fn load_folder<T: Asset + LoadableAsset + WriteableAsset>(
+        &mut self,
+        dir: &Path,
+        filter: impl Fn(&Path) -> bool,
+        watch: bool,
+    ) -> Result<Vec<AssetHandle<T>>, AssetError> {
+        let mut files: Vec<PathBuf> = fs::read_dir(self.resolve(dir))?
+            .flatten()
+            .filter(|entry| entry.file_type().is_ok_and(|ty| ty.is_file()))
+            .map(|entry| dir.join(entry.file_name()))
+            .filter(|path| filter(path))
+            .collect();
+        files.sort();
 
-                    // create/overwrite current value
-                    let loader_fn = self
-                        .reload_functions
-                        .get(&handle.ty_id)
-                        .expect("could not get loader fn");
-                    let asset = loader_fn(&path);
-                    self.cache.insert(handle.clone(), asset);
+        let options = LoadOptions {
+            sync: false,
+            watch,
+            write: false,
+        };
+        Ok(files
+            .iter()
+            .map(|path| self.load_with_options(path, options))
+            .collect())
+    }
+
+    /// Load a file with the import settings of its `.import` sidecar file
+    ///
+    /// Default settings are used if the sidecar does not exist
+    pub fn load_configurable<T: Asset + ConfigurableLoadableAsset>(
+        &mut self,
+        path: &Path,
+        sync: bool,
+    ) -> AssetHandle<T> {
+        self.load_using(path, sync, Self::load_configured::<T>)
+    }
+
+    /// Load a file with the import settings of its `.import` sidecar file
+    ///
+    /// Register asset for being watched for hot reloads, changing the sidecar also
+    /// reloads the asset. The sidecar is only watched if it exists when loading
+    pub fn load_configurable_watch<T: Asset + ConfigurableLoadableAsset>(
+        &mut self,
+        path: &Path,
+        sync: bool,
+    ) -> AssetHandle<T> {
+        let handle = self.load_configurable(path, sync);
+        self.watch_with(handle.clone(), path, Self::load_configured::<T>);
+
+        let path = self.canonical(path);
+        let sidecar = path.with_extension("import");
+        if sidecar.exists() {
+            self.watch_with(handle.clone(), &sidecar, Self::load_configured::<T>);
+            self.sidecars.insert(sidecar, path);
+        }
+        handle
+    }
+
+    /// Stop hot reloading an asset, the asset itself stays loaded
+    ///
+    /// Paths no other handle is watching are unwatched
+    pub fn unwatch<T: Asset>(&mut self, handle: &AssetHandle<T>) {
+        self.unwatch_erased(&handle.clone_typed::<DynAsset>());
+    }
+
+    // drop the reload bookkeeping of a handle and stop watching paths no other handle uses
+    fn unwatch_erased(&mut self, handle: &AssetHandle<DynAsset>) {
+        self.reload_functions.remove(handle);
+
+        let mut unwatched = Vec::new();
+        for (path, handles) in self.reload_handles.iter_mut() {
+            handles.retain(|watched| watched != handle);
+            if handles.is_empty() {
+                unwatched.push(path.clone());
+            }
+        }
+        for path in &unwatched {
+            self.reload_handles.remove(path);
+            self.sidecars.remove(path);
+        }
+        for (path, dependents) in self.dependents.iter_mut() {
+            dependents.retain(|dependent| dependent != handle);
+            if dependents.is_empty() {
+                unwatched.push(path.clone());
+            }
+        }
+        self.dependents
+            .retain(|_, dependents| !dependents.is_empty());
+
+        // a path stays watched while it is either reloaded or depended on
+        unwatched.retain(|path| {
+            !self.reload_handles.contains_key(path) && !self.dependents.contains_key(path)
+        });
+        for path in unwatched {
+            let _ = self.reload_watcher.watcher().unwatch(&path);
+            self.watched_hashes.remove(&path);
+
+            // the directory may still be needed for other files which did not exist yet
+            if let Some(dir) = self.pending_watches.remove(&path)
+                && !self.pending_watches.values().any(|pending| *pending == dir)
+                && !self.reload_handles.contains_key(&dir)
+                && !self.dependents.contains_key(&dir)
+            {
+                let _ = self.reload_watcher.watcher().unwatch(&dir);
+            }
+        }
+    }
+
+    /// Reload an asset when another file changes, e.g. a shader include
+    ///
+    /// The dependent must be watched. Dependents of assets that reload are reloaded as
+    /// well, each after the assets it depends on
+    pub fn add_dependency<T: Asset>(&mut self, dependent: &AssetHandle<T>, depends_on: &Path) {
+        let path = self.canonical(depends_on);
+        if let Err(err) = self.reload_watcher.watcher().watch(
+            &path,
+            notify_debouncer_mini::notify::RecursiveMode::NonRecursive,
+        ) {
+            self.watch_failed(path, err);
+            return;
+        }
+        if let Some(hash) = content_hash(&path) {
+            self.watched_hashes.insert(path.clone(), hash);
+        }
+
+        let dependent = dependent.clone_typed::<DynAsset>();
+        let dependents = self.dependents.entry(path).or_default();
+        if !dependents.contains(&dependent) {
+            dependents.push(dependent);
+        }
+    }
+
+    // add the reloads of dependents of changed paths and reloaded assets, ordered so
+    // every asset reloads after the assets it depends on
+    fn add_dependent_reloads(
+        &self,
+        changed: &[PathBuf],
+        reloads: &mut Vec<(PathBuf, AssetHandle<DynAsset>)>,
+    ) {
+        let mut visited: HashSet<AssetHandle<DynAsset>> =
+            reloads.iter().map(|(_, handle)| handle.clone()).collect();
+        let mut order = Vec::new();
+        for path in changed {
+            for watched in path.ancestors() {
+                self.visit_dependents(watched, &mut visited, &mut order);
+            }
+        }
+        for (path, _) in reloads.iter() {
+            self.visit_dependents(path, &mut visited, &mut order);
+        }
+
+        // dependents are pushed before the assets they depend on
+        order.reverse();
+        reloads.extend(order);
+    }
+
+    // depth first so each dependent is visited once even if the dependencies form a cycle
+    fn visit_dependents(
+        &self,
+        path: &Path,
+        visited: &mut HashSet<AssetHandle<DynAsset>>,
+        order: &mut Vec<(PathBuf, AssetHandle<DynAsset>)>,
+    ) {
+        for dependent in self.dependents.get(path).into_iter().flatten() {
+            if !visited.insert(dependent.clone()) {
+                continue;
+            }
+            let Some(load_path) = self.reload_path(dependent) else {
+                continue;
+            };
+            self.visit_dependents(&load_path, visited, order);
+            order.push((load_path, dependent.clone()));
+        }
+    }
+
+    // path a watched asset is reloaded from, None if it can not be reloaded
+    fn reload_path(&self, handle: &AssetHandle<DynAsset>) -> Option<PathBuf> {
+        if !self.reload_functions.contains_key(handle) && !self.labels.contains_key(handle) {
+            return None;
+        }
+        self.asset_paths.get(handle).cloned().or_else(|| {
+            self.reload_handles
+                .iter()
+                .find(|(path, handles)| {
+                    !self.sidecars.contains_key(*path) && handles.contains(handle)
+                })
+                .map(|(path, _)| path.clone())
+        })
+    }
+
+    fn load_configured<T: ConfigurableLoadableAsset>(path: &Path) -> Result<T, AssetLoadError> {
+        let settings = match read_file_to_string(&path.with_extension("import")) {
+            Ok(content) => T::ImportSettings::parse(&content),
+            Err(_) => T::ImportSettings::default(),
+        };
+        T::load(path, &settings)
+    }
+
+    /// Load a file of a versioned format, migrating it to the current version first
+    pub fn load_versioned<T: Asset + VersionedAsset>(
+        &mut self,
+        path: &Path,
+        sync: bool,
+    ) -> AssetHandle<T> {
+        self.load_using(path, sync, Self::load_migrated::<T>)
+    }
+
+    /// Load a file of a versioned format and register it for being watched for hot reloads
+    pub fn load_versioned_watch<T: Asset + VersionedAsset>(
+        &mut self,
+        path: &Path,
+        sync: bool,
+    ) -> AssetHandle<T> {
+        let handle = self.load_versioned(path, sync);
+        self.watch_with(handle.clone(), path, Self::load_migrated::<T>);
+        handle
+    }
+
+    fn load_migrated<T: VersionedAsset>(path: &Path) -> Result<T, AssetLoadError> {
+        let bytes = read_file(path)?;
+
+        // split off the version line
+        let line_end = bytes
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .unwrap_or(bytes.len());
+        let header = std::str::from_utf8(&bytes[..line_end])
+            .ok()
+            .and_then(|line| line.trim().strip_prefix("version "))
+            .and_then(|version| version.trim().parse::<u32>().ok());
+        let (mut version, mut data) = match header {
+            Some(version) => (version, bytes[(line_end + 1).min(bytes.len())..].to_vec()),
+            None => (1, bytes),
+        };
+
+        let current = T::current_version();
+        if version > current {
+            return Err(AssetLoadError::Parse(format!(
+                "version {} is newer than the supported version {}",
+                version, current
+            )));
+        }
+
+        let migrated = version < current;
+        while version < current {
+            data = T::migrate(version, data);
+            version += 1;
+        }
+        if migrated && T::rewrite_migrated() {
+            let mut file = format!("version {}\n", current).into_bytes();
+            file.extend_from_slice(&data);
+            fs::write(path, file)?;
+        }
+
+        T::parse(&data)
+    }
+
+    /// Load a file, setting the options on the returned builder
+    ///
+    /// E.g. `assets.load_with::<Shader>(path).watch(true).build()`
+    pub fn load_with<T: Asset + LoadableAsset + WriteableAsset>(
+        &mut self,
+        path: &Path,
+    ) -> LoadBuilder<'_, T> {
+        LoadBuilder {
+            assets: self,
+            path: path.to_path_buf(),
+            options: LoadOptions::default(),
+            ty: PhantomData,
+        }
+    }
+
+    /// Load a file with explicit options
+    pub fn load_with_options<T: Asset + LoadableAsset + WriteableAsset>(
+        &mut self,
+        path: &Path,
+        options: LoadOptions,
+    ) -> AssetHandle<T> {
+        let handle = self.load(path, options.sync);
+        if options.watch {
+            self.watch(handle.clone(), path);
+        }
+        if options.write {
+            self.write(handle.clone(), path);
+        }
+        handle
+    }
+
+    /// Load the first of several paths that exists, e.g. a mod override before the base file
+    ///
+    /// The chosen path is used for watching and writing. Paths earlier in the list
+    /// that are created later are not picked up
+    pub fn load_first_existing<T: Asset + LoadableAsset + WriteableAsset>(
+        &mut self,
+        paths: &[&Path],
+        options: LoadOptions,
+    ) -> Result<AssetHandle<T>, AssetError> {
+        let path = paths
+            .iter()
+            .find(|path| self.resolve(path).exists())
+            .ok_or_else(|| {
+                AssetError::NotFound(paths.first().map(|p| p.to_path_buf()).unwrap_or_default())
+            })?;
+        Ok(self.load_with_options(path, options))
+    }
+
+    /// Load a file with the default options of its type
+    ///
+    /// Uses the global default options if none are set for the type
+    pub fn load_default<T: Asset + LoadableAsset + WriteableAsset>(
+        &mut self,
+        path: &Path,
+    ) -> AssetHandle<T> {
+        let options = self.default_options::<T>();
+        self.load_with_options(path, options)
+    }
+
+    /// Set the options [`Assets::load_default`] uses for a type
+    pub fn set_default_options<T: Asset>(&mut self, options: LoadOptions) {
+        self.type_options.insert(TypeId::of::<T>(), options);
+    }
+
+    /// Set the options [`Assets::load_default`] uses for types without their own
+    pub fn set_global_default_options(&mut self, options: LoadOptions) {
+        self.default_options = options;
+    }
+
+    /// Get the options [`Assets::load_default`] uses for a type
+    pub fn default_options<T: Asset>(&self) -> LoadOptions {
+        self.type_options
+            .get(&TypeId::of::<T>())
+            .copied()
+            .unwrap_or(self.default_options)
+    }
+
+    /// Register asset for being watched for hot reloads
+    pub fn watch<T: Asset + LoadableAsset>(&mut self, handle: AssetHandle<T>, path: &Path) {
+        self.watch_with(handle, path, T::load);
+    }
+
+    /// Register asset for being watched for hot reloads, choosing whether a directory
+    /// is watched including its subdirectories
+    pub fn watch_with_mode<T: Asset + LoadableAsset>(
+        &mut self,
+        handle: AssetHandle<T>,
+        path: &Path,
+        mode: WatchMode,
+    ) {
+        self.watch_using(handle, path, T::load, Some(mode));
+    }
+
+    fn watch_with<T: Asset>(
+        &mut self,
+        handle: AssetHandle<T>,
+        path: &Path,
+        loader: fn(&Path) -> Result<T, AssetLoadError>,
+    ) {
+        self.watch_using(handle, path, loader, None);
+    }
+
+    fn watch_using<T: Asset>(
+        &mut self,
+        handle: AssetHandle<T>,
+        path: &Path,
+        loader: fn(&Path) -> Result<T, AssetLoadError>,
+        mode: Option<WatchMode>,
+    ) {
+        // store reload function per handle, handles of the same type may use different loaders
+        let erased = handle.clone_typed::<DynAsset>();
+        if self.watch_path(erased.clone(), path, mode) {
+            self.reload_functions.insert(
+                erased,
+                Box::new(move |path| loader(path).map(|asset| Box::new(asset) as DynAsset)),
+            );
+        }
+    }
+
+    // start watching a path and map it to the handle, false if it can not be watched
+    fn watch_path(
+        &mut self,
+        erased: AssetHandle<DynAsset>,
+        path: &Path,
+        mode: Option<WatchMode>,
+    ) -> bool {
+        let path = self.canonical(path);
+        let Some(local) = self.source.local_path(&path) else {
+            return self.watch_source_path(erased, path);
+        };
+        // files on disk share the watcher, whichever source reads them
+        let path = self.canonical(&local);
+
+        // start watching path, files which do not exist yet are picked up through their directory
+        let watched = if path.exists() {
+            let mode = mode.unwrap_or(if path.is_dir() {
+                WatchMode::Recursive
+            } else {
+                WatchMode::NonRecursive
+            });
+            self.reload_watcher.watcher().watch(&path, mode.into())
+        } else {
+            let dir = path.parent().unwrap_or(Path::new("/")).to_path_buf();
+            let watched = self.reload_watcher.watcher().watch(
+                &dir,
+                notify_debouncer_mini::notify::RecursiveMode::NonRecursive,
+            );
+            if watched.is_ok() {
+                self.pending_watches.insert(path.clone(), dir);
+            }
+            watched
+        };
+        if let Err(err) = watched {
+            self.watch_failed(path, err);
+            return false;
+        }
+
+        if let Some(hash) = content_hash(&path) {
+            self.watched_hashes.insert(path.clone(), hash);
+        }
+
+        // map path to handle
+        let handles = self.reload_handles.entry(path).or_default();
+        if !handles.contains(&erased) {
+            handles.push(erased);
+        }
+        true
+    }
+
+    // let the source report changes of a path outside the file system
+    fn watch_source_path(&mut self, erased: AssetHandle<DynAsset>, path: PathBuf) -> bool {
+        if let Err(err) = self.source.watch(&path, self.reload_sender.clone()) {
+            self.watch_failed(path, notify_debouncer_mini::notify::Error::io(err));
+            return false;
+        }
+        let handles = self.reload_handles.entry(path).or_default();
+        if !handles.contains(&erased) {
+            handles.push(erased);
+        }
+        true
+    }
+
+    fn watch_failed(&self, path: PathBuf, err: notify_debouncer_mini::notify::Error) {
+        let _ = self.watch_error_sender.send(WatchError::Watch(path, err));
+    }
+
+    /// Get the errors of the file watcher since the previous call
+    ///
+    /// Includes paths which could not be watched and errors reported while watching
+    pub fn watch_errors(&mut self) -> Vec<WatchError> {
+        self.watch_error_receiver.try_iter().collect()
+    }
+
+    /// Register asset for being written to disk when updated
+    pub fn write<T: Asset + WriteableAsset>(&mut self, handle: AssetHandle<T>, path: &Path) {
+        let path = self.canonical(path);
+        self.register_write(handle, path);
+    }
+
+    /// Redirect future writes of an asset to a new path
+    ///
+    /// The path does not need to exist yet
+    pub fn set_write_path<T: Asset + WriteableAsset>(
+        &mut self,
+        handle: AssetHandle<T>,
+        path: &Path,
+    ) {
+        let path = self.canonical(path);
+        self.register_write(handle, path);
+    }
+
+    /// Write an asset to a path without changing where it is registered to be written
+    ///
+    /// If the asset watches the path, the write does not reload it. Shared assets are
+    /// copied first like by [`Assets::get_mut`], without copy on write the write fails
+    pub fn write_as<T: Asset + WriteableAsset>(
+        &mut self,
+        handle: &AssetHandle<T>,
+        path: &Path,
+    ) -> Result<(), AssetError> {
+        let path = self.resolve(path);
+        let erased = handle.clone_typed::<DynAsset>();
+        if !self.cache.contains_key(&erased) {
+            return Err(AssetError::NotLoaded);
+        }
+        if !self.unshare(&erased) {
+            return Err(AssetError::Shared);
+        }
+        let asset = self
+            .cache
+            .get_mut(&erased)
+            .expect("could not get unshared asset");
+        Arc::get_mut(asset)
+            .expect("could not get unshared asset")
+            .as_any_mut()
+            .downcast_mut::<T>()
+            .expect("could not cast during write")
+            .write(&path);
+        self.ignore_own_write(&erased, &path);
+        Ok(())
+    }
+
+    /// Write an asset to any path, keeping the paths it is reloaded from and written to
+    ///
+    /// Like [`Assets::write_as`], but failures are reported by [`Assets::write_error`]
+    pub fn write_to<T: Asset + WriteableAsset>(&mut self, handle: AssetHandle<T>, path: &Path) {
+        if let Err(err) = self.write_as(&handle, path) {
+            self.write_errors
+                .insert(handle.erase(), std::io::Error::other(err));
+        }
+    }
+
+    // remember the written content so the change event is seen as unchanged,
+    // unless other assets watch the path and need to reload
+    fn ignore_own_write(&mut self, handle: &AssetHandle<DynAsset>, path: &Path) {
+        let path = self.canonical(path);
+        let only_watcher = self
+            .reload_handles
+            .get(&path)
+            .is_some_and(|handles| handles.iter().all(|watched| watched == handle));
+        if only_watcher
+            && !self.dependents.contains_key(&path)
+            && let Some(hash) = content_hash(&path)
+        {
+            self.watched_hashes.insert(path, hash);
+        }
+    }
+
+    /// Register asset for being appended to a file when updated
+    ///
+    /// Only entries added since the previous append, or since the asset was loaded, are written
+    pub fn append<T: Asset + AppendableAsset>(&mut self, handle: AssetHandle<T>, path: &Path) {
+        let path = self.canonical(path);
+        let erased = handle.clone_typed::<DynAsset>();
+        self.load_handles.insert(erased.clone(), path);
+        self.append_types.insert(
+            TypeId::of::<T>(),
+            AppendType {
+                append: Self::append_erased::<T>,
+                entries: Self::entries_erased::<T>,
+            },
+        );
+        self.append_written.insert(erased.clone(), 0);
+        self.reset_appended(&erased);
+    }
+
+    /// Load asset and register it for being appended to its file when updated
+    pub fn load_append<T: Asset + LoadableAsset + AppendableAsset>(
+        &mut self,
+        path: &Path,
+        sync: bool,
+    ) -> AssetHandle<T> {
+        let handle = self.load(path, sync);
+        self.append(handle.clone(), path);
+        handle
+    }
+
+    fn append_erased<T: Asset + AppendableAsset>(
+        asset: &dyn Asset,
+        from: usize,
+        file: &mut fs::File,
+    ) {
+        asset
+            .as_any()
+            .downcast_ref::<T>()
+            .expect("could not cast during append")
+            .append(from, file);
+    }
+
+    fn entries_erased<T: Asset + AppendableAsset>(asset: &dyn Asset) -> usize {
+        asset
+            .as_any()
+            .downcast_ref::<T>()
+            .expect("could not cast during append")
+            .entries()
+    }
+
+    // the file matches the asset after loading, so only later entries are appended
+    fn reset_appended(&mut self, handle: &AssetHandle<DynAsset>) {
+        let (Some(written), Some(asset)) =
+            (self.append_written.get_mut(handle), self.cache.get(handle))
+        else {
+            return;
+        };
+        *written = (self.append_types[&handle.ty_id].entries)(asset.as_ref());
+    }
+
+    fn register_write<T: Asset + WriteableAsset>(&mut self, handle: AssetHandle<T>, path: PathBuf) {
+        // map handle to path
+        self.load_handles
+            .insert(handle.clone_typed::<DynAsset>(), path);
+
+        // store write function
+        self.write_functions
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| {
+                Box::new(|asset, path| {
+                    let typed = asset
+                        .as_any_mut()
+                        .downcast_mut::<T>()
+                        .expect("could not cast during write");
+                    typed.write(path);
+                })
+            });
+    }
+
+    /// Get all handles that are watched or written at a path
+    pub fn handles_for_path(&self, path: &Path) -> Vec<AssetHandle<DynAsset>> {
+        let path = self.canonical(path);
+
+        let mut handles = self.reload_handles.get(&path).cloned().unwrap_or_default();
+        for (handle, write_path) in self.load_handles.iter() {
+            if *write_path == path && !handles.contains(handle) {
+                handles.push(handle.clone());
+            }
+        }
+        handles
+    }
+
+    /// Get the canonicalized path an asset is loaded, written or watched under
+    ///
+    /// Reloads only fire for changes to this exact path
+    pub fn canonical_path<T: Asset>(&self, handle: &AssetHandle<T>) -> Option<&Path> {
+        let erased = handle.clone_typed::<DynAsset>();
+        self.asset_paths
+            .get(&erased)
+            .or_else(|| self.load_handles.get(&erased))
+            .or_else(|| {
+                self.reload_handles
+                    .iter()
+                    .find(|(_, handles)| handles.contains(&erased))
+                    .map(|(path, _)| path)
+            })
+            .map(PathBuf::as_path)
+    }
+
+    /// Get the current modification time of the file an asset was loaded from
+    pub fn file_mtime<T: Asset>(&self, handle: &AssetHandle<T>) -> Option<SystemTime> {
+        let path = self.asset_paths.get(&handle.clone_typed::<DynAsset>())?;
+        modified_time(path)
+    }
+
+    /// Check if the file an asset was loaded from changed since it was last loaded
+    ///
+    /// Compares modification times, so this works without the file watcher
+    pub fn is_stale<T: Asset>(&self, handle: &AssetHandle<T>) -> bool {
+        let erased = handle.clone_typed::<DynAsset>();
+        let Some(path) = self.asset_paths.get(&erased) else {
+            return false;
+        };
+        if self.load_pending.contains(&erased) {
+            return false;
+        }
+        modified_time(path) != self.load_mtimes.get(&erased).copied()
+    }
+
+    /// Limit how many async loads run at the same time
+    ///
+    /// Can be changed at any time, e.g. lowered during gameplay and raised on loading
+    /// screens. Loads over the limit wait until others finish
+    pub fn set_load_concurrency(&mut self, concurrency: usize) {
+        self.load_limiter.set_limit(concurrency.max(1));
+    }
+
+    /// Stop starting async loads while the loaded but not yet delivered bytes exceed a limit
+    ///
+    /// Sizes are estimated by the file size. Loads resume as poll_loaded delivers
+    /// completed loads. None removes the limit
+    pub fn set_inflight_byte_limit(&mut self, limit: Option<u64>) {
+        self.byte_limiter.set_limit(limit);
+    }
+
+    /// Get the estimated bytes of async loads which are loading or waiting for poll_loaded
+    pub fn inflight_bytes(&self) -> u64 {
+        self.byte_limiter.inflight()
+    }
+
+    /// Delay every async load, e.g. to test how loading states are handled
+    pub fn set_simulated_delay(&mut self, delay: Option<Duration>) {
+        self.simulated_delay = delay;
+    }
+
+    /// Run a callback once an async load is delivered by poll_loaded
+    ///
+    /// Runs immediately if the asset is already loaded. Callbacks of the same handle run in
+    /// registration order, callbacks of failed loads are dropped without running
+    pub fn on_loaded<T: Asset>(
+        &mut self,
+        handle: AssetHandle<T>,
+        f: impl FnOnce(&T) + Send + 'static,
+    ) {
+        if let Some(asset) = self.get(handle.clone()) {
+            f(asset);
+            return;
+        }
+
+        self.load_callbacks
+            .entry(handle.clone_typed::<DynAsset>())
+            .or_default()
+            .push(Box::new(move |asset| {
+                f(asset
+                    .as_any()
+                    .downcast_ref::<T>()
+                    .expect("could not downcast"))
+            }));
+    }
+
+    /// Discard the result of an async load that is still in flight
+    ///
+    /// Loads which have not started yet are skipped
+    pub fn cancel_load<T: Asset>(&mut self, handle: AssetHandle<T>) {
+        let erased = handle.clone_typed::<DynAsset>();
+        if self.load_pending.remove(&erased) {
+            if let Some(cancelled) = self.load_cancel_flags.get(&erased) {
+                cancelled.store(true, SeqCst);
+            }
+            self.load_cancelled.insert(erased);
+        }
+    }
+
+    /// Discard the results of every async load that is still in flight
+    pub fn cancel_all_loads(&mut self) {
+        for cancelled in self.load_cancel_flags.values() {
+            cancelled.store(true, SeqCst);
+        }
+        self.load_cancelled.extend(self.load_pending.drain());
+    }
+
+    // flag checked by a load job before it starts
+    fn cancel_flag(&mut self, handle: &AssetHandle<DynAsset>) -> Arc<AtomicBool> {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.load_cancel_flags
+            .insert(handle.clone(), cancelled.clone());
+        cancelled
+    }
+
+    //
+    // Singletons
+    //
+
+    /// Insert an asset as the single canonical asset of its type
+    pub fn set_singleton<T: Asset>(&mut self, data: T) -> AssetHandle<T> {
+        let handle = self.insert(data);
+        self.singletons
+            .insert(TypeId::of::<T>(), handle.clone_typed::<DynAsset>());
+        handle
+    }
+
+    /// Load a file as the single canonical asset of its type
+    pub fn load_singleton<T: Asset + LoadableAsset>(
+        &mut self,
+        path: &Path,
+        sync: bool,
+    ) -> AssetHandle<T> {
+        let handle = self.load(path, sync);
+        self.singletons
+            .insert(TypeId::of::<T>(), handle.clone_typed::<DynAsset>());
+        handle
+    }
+
+    /// Get the handle of the canonical asset of a type
+    pub fn singleton_handle<T: Asset>(&self) -> Option<AssetHandle<T>> {
+        self.singletons
+            .get(&TypeId::of::<T>())
+            .map(|handle| self.tracked(handle.clone_typed::<T>()))
+    }
+
+    /// Get the canonical asset of a type
+    pub fn singleton<T: Asset>(&self) -> Option<&T> {
+        let handle = self.singleton_handle::<T>()?;
+        self.get(handle)
+    }
+
+    //
+    // Names
+    //
+
+    /// Insert an asset under a name
+    pub fn insert_named<T: Asset>(&mut self, name: &str, data: T) -> AssetHandle<T> {
+        let handle = self.insert(data);
+        self.set_name(&handle, name);
+        handle
+    }
+
+    /// Name an asset, replacing its previous name
+    ///
+    /// A handle already owning the name loses it
+    pub fn set_name<T: Asset>(&mut self, handle: &AssetHandle<T>, name: &str) {
+        self.set_name_erased(handle.clone_typed(), name);
+    }
+
+    fn set_name_erased(&mut self, erased: AssetHandle<DynAsset>, name: &str) {
+        if let Some(previous) = self.handle_names.remove(&erased) {
+            self.names.remove(&previous);
+        }
+        if let Some(owner) = self.names.insert(name.to_string(), erased.clone()) {
+            self.handle_names.remove(&owner);
+        }
+        self.handle_names.insert(erased, name.to_string());
+    }
+
+    /// Add another name an asset can be looked up by
+    ///
+    /// A handle already owning the alias loses it
+    pub fn add_alias<T: Asset>(&mut self, handle: &AssetHandle<T>, alias: &str) {
+        let erased = handle.clone_typed::<DynAsset>();
+        if let Some(owner) = self.aliases.insert(alias.to_string(), erased.clone())
+            && let Some(aliases) = self.handle_aliases.get_mut(&owner)
+        {
+            aliases.retain(|owned| owned != alias);
+        }
+        self.handle_aliases
+            .entry(erased)
+            .or_default()
+            .push(alias.to_string());
+    }
+
+    /// Get the handle owning a name or alias, None if it is owned by another type
+    pub fn named<T: Asset>(&self, name: &str) -> Option<AssetHandle<T>> {
+        self.names
+            .get(name)
+            .or_else(|| self.aliases.get(name))
+            .and_then(|handle| handle.downcast::<T>())
+            .map(|handle| self.tracked(handle))
+    }
+
+    pub fn name_of<T: Asset>(&self, handle: &AssetHandle<T>) -> Option<&str> {
+        self.handle_names
+            .get(&handle.clone_typed::<DynAsset>())
+            .map(String::as_str)
+    }
+
+    pub fn aliases_of<T: Asset>(&self, handle: &AssetHandle<T>) -> Vec<&str> {
+        self.handle_aliases
+            .get(&handle.clone_typed::<DynAsset>())
+            .map(|aliases| aliases.iter().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    //
+    // Labeled assets
+    //
+
+    /// Load every labeled asset of a file
+    ///
+    /// Each asset is named `path#label`, e.g. `assets/scene.gltf#Mesh0`, and can be looked
+    /// up through [`Assets::named`]. Loading a file which is already loaded returns the
+    /// existing handles
+    pub fn load_labeled<T: MultiAsset + 'static>(
+        &mut self,
+        path: &Path,
+    ) -> Result<HashMap<String, AssetHandle<DynAsset>>, AssetLoadError> {
+        let canonical = self.canonical(path);
+
+        // loading the same file again shares the handles of every label
+        let key = (canonical.clone(), TypeId::of::<T>());
+        if let Some(existing) = self.path_to_handle.get(&key)
+            && self.cache.contains_key(existing)
+        {
+            let existing: Vec<_> = self
+                .labels
+                .iter()
+                .filter(|(handle, _)| {
+                    handle.path() == Some(canonical.as_path()) && self.cache.contains_key(*handle)
+                })
+                .map(|(handle, label)| (label.clone(), handle.clone_typed()))
+                .collect();
+            return Ok(existing
+                .into_iter()
+                .map(|(label, handle)| (label, self.track(handle)))
+                .collect());
+        }
+
+        let assets = with_context(&self.load_context(), || T::load_all(&canonical))
+            .map_err(|err| err.at_path(&canonical))?;
+        self.labeled_loaders.insert(canonical.clone(), T::load_all);
+
+        let mut handles = HashMap::new();
+        for (label, asset) in assets {
+            let ty_id = asset.as_ref().as_any().type_id();
+            let handle = self.track(AssetHandle::new_erased(ty_id).with_path(&canonical));
+            self.cache.insert(handle.clone_typed(), Arc::from(asset));
+            self.changes.loaded.insert(handle.clone_typed());
+            self.set_name_erased(
+                handle.clone_typed(),
+                &format!("{}#{}", path.display(), label),
+            );
+            self.labels.insert(handle.clone_typed(), label.clone());
+            self.path_to_handle
+                .entry(key.clone())
+                .or_insert_with(|| handle.clone_typed());
+            handles.insert(label, handle);
+        }
+        Ok(handles)
+    }
+
+    /// Load every labeled asset of a file
+    ///
+    /// Register the assets for being watched for hot reloads, the file is loaded once
+    /// per reload and every asset is updated under its label
+    pub fn load_labeled_watch<T: MultiAsset + 'static>(
+        &mut self,
+        path: &Path,
+    ) -> Result<HashMap<String, AssetHandle<DynAsset>>, AssetLoadError> {
+        let handles = self.load_labeled::<T>(path)?;
+        for handle in handles.values() {
+            self.watch_path(handle.clone_typed(), path, None);
+        }
+        Ok(handles)
+    }
+
+    // load a labeled file once per poll_reload and take the asset of a label
+    fn reload_labeled(
+        &self,
+        path: &Path,
+        handle: &AssetHandle<DynAsset>,
+        loads: &mut HashMap<PathBuf, Result<HashMap<String, DynAsset>, String>>,
+    ) -> Result<DynAsset, AssetLoadError> {
+        let label = &self.labels[handle];
+        let Some(load_all) = self.labeled_loaders.get(path) else {
+            return Err(AssetLoadError::NotFound(path.to_path_buf()));
+        };
+        let loaded = loads.entry(path.to_path_buf()).or_insert_with(|| {
+            load_all(path)
+                .map(|assets| assets.into_iter().collect())
+                .map_err(|err| err.to_string())
+        });
+        let assets = loaded
+            .as_mut()
+            .map_err(|err| AssetLoadError::Parse(err.clone()))?;
+        match assets.remove(label) {
+            Some(asset) if asset.as_ref().as_any().type_id() == handle.ty_id => Ok(asset),
+            Some(_) => Err(AssetLoadError::Parse(format!(
+                "label {} changed type",
+                label
+            ))),
+            None => Err(AssetLoadError::Parse(format!("missing label {}", label))),
+        }
+    }
+
+    //
+    // Persistent ids
+    //
+
+    /// Get the id of a loaded asset which stays the same across sessions
+    ///
+    /// Only assets loaded from a path have one, and only if their type was registered
+    /// through [`Assets::register_type`] before loading
+    pub fn persistent_id<T: Asset>(&self, handle: &AssetHandle<T>) -> Option<PersistentId> {
+        self.handle_persistent_ids
+            .get(&handle.clone_typed::<DynAsset>())
+            .copied()
+    }
+
+    /// Get the handle of the asset loaded under a persistent id in this session
+    pub fn resolve_persistent(&self, id: PersistentId) -> Option<AssetHandle<DynAsset>> {
+        self.persistent_ids
+            .get(&id)
+            .map(|handle| self.tracked(handle.clone()))
+    }
+
+    fn register_persistent<T: Asset>(&mut self, erased: &AssetHandle<DynAsset>, path: &Path) {
+        // type_name is not stable between compiler versions, the registered name is
+        let Some(type_name) = self.type_names.get(&TypeId::of::<T>()) else {
+            return;
+        };
+
+        // relative to the root so moving the whole project keeps the ids
+        let root = self.canonical(Path::new("."));
+        let relative = path.strip_prefix(&root).unwrap_or(path);
+        let key = format!(
+            "{}:{}",
+            relative.to_string_lossy().replace('\\', "/"),
+            type_name
+        );
+        let id = PersistentId(stable_hash(key.as_bytes()));
+        self.persistent_ids.insert(id, erased.clone());
+        self.handle_persistent_ids.insert(erased.clone(), id);
+    }
+
+    //
+    // Metadata
+    //
+
+    /// Attach a value to an asset, independent of its content
+    ///
+    /// The metadata is cleared when the asset is removed
+    pub fn set_meta<T: Asset>(&mut self, handle: &AssetHandle<T>, key: &str, value: String) {
+        self.meta
+            .entry(handle.clone_typed::<DynAsset>())
+            .or_default()
+            .insert(key.to_string(), value);
+    }
+
+    pub fn get_meta<T: Asset>(&self, handle: &AssetHandle<T>, key: &str) -> Option<&str> {
+        self.meta
+            .get(&handle.clone_typed::<DynAsset>())
+            .and_then(|meta| meta.get(key))
+            .map(String::as_str)
+    }
+
+    pub fn remove_meta<T: Asset>(&mut self, handle: &AssetHandle<T>, key: &str) -> Option<String> {
+        self.meta
+            .get_mut(&handle.clone_typed::<DynAsset>())
+            .and_then(|meta| meta.remove(key))
+    }
+
+    //
+    // Scopes
+    //
+
+    /// Create a scope which assets can be loaded into
+    pub fn new_scope(&mut self) -> AssetScope {
+        let scope = AssetScope(self.next_scope);
+        self.next_scope += 1;
+        self.scopes.insert(scope, ScopeEntry::default());
+        scope
+    }
+
+    /// Create a scope which is unloaded together with its parent
+    pub fn new_child_scope(&mut self, parent: AssetScope) -> AssetScope {
+        let scope = self.new_scope();
+        if let Some(entry) = self.scopes.get_mut(&scope) {
+            entry.parent = Some(parent);
+        }
+        scope
+    }
+
+    /// Load a file into a scope
+    pub fn load_in<T: Asset + LoadableAsset>(
+        &mut self,
+        scope: AssetScope,
+        path: &Path,
+        sync: bool,
+    ) -> AssetHandle<T> {
+        let handle = self.load(path, sync);
+        self.add_to_scope(scope, &handle);
+        handle
+    }
+
+    /// Add an asset to a scope so it is unloaded with it
+    pub fn add_to_scope<T: Asset>(&mut self, scope: AssetScope, handle: &AssetHandle<T>) {
+        if let Some(entry) = self.scopes.get_mut(&scope) {
+            entry.handles.push(handle.clone_typed::<DynAsset>());
+        }
+    }
+
+    /// Remove every asset in a scope and its child scopes
+    ///
+    /// Pending loads of the assets are discarded when they complete
+    pub fn unload_scope(&mut self, scope: AssetScope) {
+        let children: Vec<AssetScope> = self
+            .scopes
+            .iter()
+            .filter(|(_, entry)| entry.parent == Some(scope))
+            .map(|(child, _)| *child)
+            .collect();
+        for child in children {
+            self.unload_scope(child);
+        }
+
+        if let Some(entry) = self.scopes.remove(&scope) {
+            for handle in entry.handles {
+                self.remove_erased(&handle);
+            }
+        }
+    }
+
+    /// Run a callback with every asset of a type right before it is removed
+    ///
+    /// Callbacks registered for the same type run in registration order
+    pub fn on_removed<T: Asset>(&mut self, f: impl Fn(&T) + Send + 'static) {
+        self.remove_functions
+            .entry(TypeId::of::<T>())
+            .or_default()
+            .push(Box::new(move |asset| {
+                f(asset
+                    .as_any()
+                    .downcast_ref::<T>()
+                    .expect("could not downcast"))
+            }));
+    }
+
+    /// Remove an asset and return the owned value
+    ///
+    /// Stops watching its path if no other handle uses it. Assets still shared through
+    /// [`Assets::get_arc`] or other handles are copied if their type is registered for
+    /// copy on write, otherwise the asset is removed and None is returned
+    pub fn remove<T: Asset>(&mut self, handle: AssetHandle<T>) -> Option<T> {
+        let asset = self.remove_erased(&handle.clone_typed::<DynAsset>())?;
+        self.take_owned(asset)
+    }
+
+    /// Remove every asset, cancel pending loads and stop watching all paths
+    ///
+    /// Functions registered per type, e.g. for writing, are kept so assets can be loaded again
+    pub fn clear(&mut self) {
+        self.cancel_all_loads();
+
+        let mut handles: HashSet<AssetHandle<DynAsset>> = self.cache.keys().cloned().collect();
+        handles.extend(self.load_cancelled.iter().cloned());
+        handles.extend(self.load_handles.keys().cloned());
+        handles.extend(self.load_errors.keys().cloned());
+        handles.extend(self.reload_handles.values().flatten().cloned());
+        handles.extend(self.dependents.values().flatten().cloned());
+        for handle in handles {
+            self.remove_erased(&handle);
+        }
+
+        self.clear_render_cache();
+    }
+
+    /// Remove the assets whose handles were all dropped
+    ///
+    /// Handles returned by inserting, loading or looking up an asset keep it alive, as do
+    /// their clones. Returns how many assets were removed
+    pub fn poll_dropped(&mut self) -> usize {
+        let dropped: Vec<AssetHandle<DynAsset>> = self.dropped_receiver.try_iter().collect();
+
+        let mut removed = 0;
+        for handle in dropped {
+            // the asset may have been handed out again since
+            if self
+                .handle_refs
+                .get(&handle.id)
+                .is_some_and(|refs| refs.strong_count() > 0)
+            {
+                continue;
+            }
+            if self.remove_erased(&handle).is_some() {
+                removed += 1;
+            }
+        }
+        removed
+    }
+
+    // hand out a handle keeping the asset alive, shared with the handles handed out before
+    fn track<T>(&mut self, handle: AssetHandle<T>) -> AssetHandle<T> {
+        if let Some(refs) = self.handle_refs.get(&handle.id).and_then(Weak::upgrade) {
+            return AssetHandle {
+                refs: Some(refs),
+                ..handle
+            };
+        }
+
+        let refs = Arc::new(HandleRef::new(&handle, self.dropped_sender.clone()));
+        self.handle_refs.insert(handle.id, Arc::downgrade(&refs));
+        AssetHandle {
+            refs: Some(refs),
+            ..handle
+        }
+    }
+
+    // like track, but only joins handles which are still alive
+    fn tracked<T>(&self, handle: AssetHandle<T>) -> AssetHandle<T> {
+        let refs = self.handle_refs.get(&handle.id).and_then(Weak::upgrade);
+        AssetHandle { refs, ..handle }
+    }
+
+    // remove all traces of an asset
+    fn remove_erased(&mut self, handle: &AssetHandle<DynAsset>) -> Option<SharedAsset> {
+        if self.load_pending.contains(handle) {
+            if let Some(cancelled) = self.load_cancel_flags.get(handle) {
+                cancelled.store(true, SeqCst);
+            }
+            self.load_cancelled.insert(handle.clone());
+        }
+
+        self.render_cache.retain(|key, _| key.source != *handle);
+        self.render_generations
+            .retain(|(source, _), _| source != handle);
+        self.render_versions.retain(|key, _| key.source != *handle);
+        self.convert_errors.retain(|key, _| key.source != *handle);
+        self.convert_misses
+            .retain(|(source, _), _| source != handle);
+        self.convert_hits.retain(|(source, _), _| source != handle);
+        self.render_sources.retain(|_, key| key.source != *handle);
+        self.tracked_converts
+            .retain(|(source, _), _| source != handle);
+        self.source_versions.remove(handle);
+        self.convert_pending.retain(|key, _| key.source != *handle);
+        self.content_hashes.remove(handle);
+        self.load_errors.remove(handle);
+        self.load_callbacks.remove(handle);
+        self.labels.remove(handle);
+        if let Some(id) = self.handle_persistent_ids.remove(handle)
+            && self.persistent_ids.get(&id) == Some(handle)
+        {
+            self.persistent_ids.remove(&id);
+        }
+        self.path_to_handle.retain(|_, loaded| loaded != handle);
+        self.asset_paths.remove(handle);
+        self.load_mtimes.remove(handle);
+        self.load_dirty.remove(handle);
+        self.frozen.remove(handle);
+        self.load_handles.remove(handle);
+        self.append_written.remove(handle);
+        self.write_errors.remove(handle);
+        self.written_callbacks.remove(handle);
+        self.singletons.retain(|_, singleton| singleton != handle);
+        self.meta.remove(handle);
+        if let Some(name) = self.handle_names.remove(handle) {
+            self.names.remove(&name);
+        }
+        for alias in self.handle_aliases.remove(handle).unwrap_or_default() {
+            self.aliases.remove(&alias);
+        }
+        self.explicit_ids.remove(&handle.id);
+        if self
+            .handle_refs
+            .get(&handle.id)
+            .is_some_and(|refs| refs.strong_count() == 0)
+        {
+            self.handle_refs.remove(&handle.id);
+        }
+        for entry in self.scopes.values_mut() {
+            entry.handles.retain(|scoped| scoped != handle);
+        }
+        self.unwatch_erased(handle);
+
+        let asset = self.cache.remove(handle)?;
+        for remove_fn in self
+            .remove_functions
+            .get(&handle.ty_id)
+            .into_iter()
+            .flatten()
+        {
+            remove_fn(asset.as_ref());
+        }
+        Some(asset)
+    }
+
+    //
+    // Manifests
+    //
+
+    /// Register a type so it can be loaded by name from a manifest
+    ///
+    /// The name is also part of the [`PersistentId`] of assets of the type
+    pub fn register_type<T: Asset + LoadableAsset>(&mut self, name: &str) {
+        self.type_names.insert(TypeId::of::<T>(), name.to_string());
+        self.registered_types.insert(
+            name.to_string(),
+            RegisteredType {
+                load: Self::load_erased::<T>,
+                watch: Self::watch_erased::<T>,
+                write: None,
+            },
+        );
+    }
+
+    /// Register a type so it can be loaded and written by name from a manifest
+    pub fn register_writeable_type<T: Asset + LoadableAsset + WriteableAsset>(
+        &mut self,
+        name: &str,
+    ) {
+        self.type_names.insert(TypeId::of::<T>(), name.to_string());
+        self.registered_types.insert(
+            name.to_string(),
+            RegisteredType {
+                load: Self::load_erased::<T>,
+                watch: Self::watch_erased::<T>,
+                write: Some(Self::write_erased::<T>),
+            },
+        );
+    }
+
+    /// Load all assets listed in a manifest file
+    ///
+    /// Each line is `name type path [watch] [write] [sync]` where `type` is a registered
+    /// type name and `path` is relative to the manifest. Empty lines and lines starting
+    /// with `#` are skipped. Entries are loaded async unless `sync` is given
+    ///
+    /// Failing entries do not stop the rest from loading, they are collected in
+    /// [`AssetError::Manifest`] together with the handles that did load
+    pub fn load_manifest(
+        &mut self,
+        manifest: &Path,
+    ) -> Result<HashMap<String, AssetHandle<DynAsset>>, AssetError> {
+        let manifest = self.resolve(manifest);
+        let content = fs::read_to_string(&manifest)?;
+        let dir = manifest.parent().unwrap_or(Path::new(""));
+
+        let mut handles = HashMap::new();
+        let mut errors = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match self.load_manifest_entry(dir, line) {
+                Ok((name, handle)) => {
+                    handles.insert(name, handle);
+                }
+                Err(err) => errors.push((i + 1, err)),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(handles)
+        } else {
+            Err(AssetError::Manifest { handles, errors })
+        }
+    }
+
+    fn load_manifest_entry(
+        &mut self,
+        dir: &Path,
+        line: &str,
+    ) -> Result<(String, AssetHandle<DynAsset>), AssetError> {
+        let mut split = line.split_whitespace();
+        let (Some(name), Some(ty), Some(path)) = (split.next(), split.next(), split.next()) else {
+            return Err(AssetError::Parse(format!(
+                "expected `name type path`, got `{}`",
+                line
+            )));
+        };
+
+        let (mut watch, mut write, mut sync) = (false, false, false);
+        for flag in split {
+            match flag {
+                "watch" => watch = true,
+                "write" => write = true,
+                "sync" => sync = true,
+                _ => return Err(AssetError::Parse(format!("unknown flag `{}`", flag))),
+            }
+        }
+
+        let registered = *self
+            .registered_types
+            .get(ty)
+            .ok_or_else(|| AssetError::UnknownType(ty.to_string()))?;
+        let write_fn = match (write, registered.write) {
+            (true, None) => return Err(AssetError::NotWriteable(ty.to_string())),
+            (true, Some(write_fn)) => Some(write_fn),
+            (false, _) => None,
+        };
+
+        let path = dir.join(path);
+        if !path.exists() {
+            return Err(AssetError::NotFound(path));
+        }
+
+        let handle = (registered.load)(self, &path, sync);
+        if watch {
+            (registered.watch)(self, handle.clone(), &path);
+        }
+        if let Some(write_fn) = write_fn {
+            write_fn(self, handle.clone(), &path);
+        }
+
+        Ok((name.to_string(), handle))
+    }
+
+    fn load_erased<T: Asset + LoadableAsset>(
+        &mut self,
+        path: &Path,
+        sync: bool,
+    ) -> AssetHandle<DynAsset> {
+        self.load::<T>(path, sync).erase()
+    }
+
+    fn watch_erased<T: Asset + LoadableAsset>(
+        &mut self,
+        handle: AssetHandle<DynAsset>,
+        path: &Path,
+    ) {
+        self.watch(handle.clone_typed::<T>(), path);
+    }
+
+    fn write_erased<T: Asset + WriteableAsset>(
+        &mut self,
+        handle: AssetHandle<DynAsset>,
+        path: &Path,
+    ) {
+        self.write(handle.clone_typed::<T>(), path);
+    }
+
+    //
+    // Snapshots
+    //
+
+    /// Register a type so it is stored in and restored from snapshots
+    ///
+    /// The name identifies the type in the snapshot file and must stay the same across builds
+    pub fn register_snapshot_type<T: Asset + LoadableAsset + SnapshotableAsset>(
+        &mut self,
+        name: &str,
+    ) {
+        self.snapshot_types.insert(
+            name.to_string(),
+            SnapshotType {
+                save: Self::save_snapshot_erased::<T>,
+                restore: Self::restore_snapshot_erased::<T>,
+                reload: Self::reload_snapshot_erased::<T>,
+            },
+        );
+        self.snapshot_names
+            .insert(TypeId::of::<T>(), name.to_string());
+    }
+
+    /// Store every cached asset of a snapshot type together with its handle id and path
+    ///
+    /// Returns the number of assets stored
+    pub fn save_snapshot(&self, path: &Path) -> Result<usize, AssetError> {
+        let mut entries = Vec::new();
+        for (handle, asset) in self.cache.iter() {
+            let Some(name) = self.snapshot_names.get(&handle.ty_id) else {
+                continue;
+            };
+            let save = self.snapshot_types[name].save;
+            entries.push(SnapshotEntry {
+                ty: name.clone(),
+                id: handle.id,
+                path: self.asset_paths.get(handle).cloned(),
+                modified: self.load_mtimes.get(handle).copied(),
+                data: save(asset.as_ref()),
+            });
+        }
+
+        fs::write(self.resolve(path), snapshot::encode(&entries))?;
+        Ok(entries.len())
+    }
+
+    /// Restore the assets of a snapshot under their original handle ids
+    ///
+    /// Assets whose file changed since the snapshot was saved are loaded from the file instead,
+    /// unless the file fails to load
+    pub fn load_snapshot(&mut self, path: &Path) -> Result<Vec<AssetHandle<DynAsset>>, AssetError> {
+        let bytes = fs::read(self.resolve(path))?;
+        let entries = snapshot::decode(&bytes)?;
+
+        let mut handles = Vec::new();
+        for entry in entries {
+            let ty = *self
+                .snapshot_types
+                .get(&entry.ty)
+                .ok_or_else(|| AssetError::UnknownType(entry.ty.clone()))?;
+
+            let path = entry.path;
+            let current = path.as_deref().and_then(modified_time);
+            let reloaded = match &path {
+                Some(path) if current.is_some() && current != entry.modified => {
+                    (ty.reload)(self, entry.id, path)
+                }
+                _ => None,
+            };
+            let handle = match reloaded {
+                Some(handle) => handle,
+                None => (ty.restore)(self, entry.id, &entry.data).ok_or_else(|| {
+                    AssetError::Parse(format!("invalid snapshot data for {}", entry.ty))
+                })?,
+            };
+
+            if let Some(modified) = current.or(entry.modified) {
+                self.load_mtimes.insert(handle.clone_typed(), modified);
+            }
+            match path {
+                Some(path) => {
+                    self.asset_paths.insert(handle.clone_typed(), path.clone());
+                    handles.push(handle.with_path(&path));
+                }
+                None => handles.push(handle),
+            }
+        }
+        Ok(handles)
+    }
+
+    fn save_snapshot_erased<T: Asset + SnapshotableAsset>(asset: &dyn Asset) -> Vec<u8> {
+        asset
+            .as_any()
+            .downcast_ref::<T>()
+            .expect("could not downcast")
+            .to_snapshot()
+    }
+
+    fn restore_snapshot_erased<T: Asset + SnapshotableAsset>(
+        &mut self,
+        id: u64,
+        bytes: &[u8],
+    ) -> Option<AssetHandle<DynAsset>> {
+        let asset = T::from_snapshot(bytes)?;
+        Some(self.insert_with_id(id, asset).erase())
+    }
+
+    fn reload_snapshot_erased<T: Asset + LoadableAsset>(
+        &mut self,
+        id: u64,
+        path: &Path,
+    ) -> Option<AssetHandle<DynAsset>> {
+        let asset = with_context(&self.load_context(), || T::load(path)).ok()?;
+        Some(self.insert_with_id(id, asset).erase())
+    }
+
+    //
+    // Render assets
+    //
+
+    pub fn convert<G: ConvertableRenderAsset>(
+        &mut self,
+        handle: AssetHandle<G::SourceAsset>,
+        params: &G::Params,
+    ) -> Option<ArcHandle<G>> {
+        // create new if not in cache
+        let erased = handle.clone_typed::<DynAsset>();
+        let key = RenderKey::new::<G>(erased.clone(), params);
+        self.discard_stale_render(&key);
+        let counter = (erased.clone(), TypeId::of::<G>());
+        if !self.render_cache.contains_key(&key) {
+            let shared_key = (self.source_key(&erased), key.ty, key.params);
+            let shared = self.shared_render_cache.as_ref().and_then(|cache| {
+                let cache = cache.lock().expect("could not lock shared render cache");
+                cache.entries.get(&shared_key).cloned()
+            });
+
+            // sources with identical file content share one render asset
+            let content_key = self
+                .content_renders
+                .as_ref()
+                .and(self.content_hashes.get(&erased))
+                .map(|hash| (*hash, key.ty, key.params));
+            let identical = content_key.and_then(|content_key| {
+                self.content_renders
+                    .as_ref()
+                    .and_then(|renders| renders.get(&content_key).cloned())
+            });
+
+            if let Some(render) = shared.or(identical) {
+                self.insert_render(key.clone(), render);
+            } else if self.convert_failed(&key) {
+                return None;
+            } else if let Some(asset) = self.get(handle) {
+                let converted = G::convert(asset, params);
+                *self.convert_misses.entry(counter).or_insert(0) += 1;
+                let converted: DynRenderAsset = match converted {
+                    Ok(converted) => ArcHandle::new(converted).upcast(),
+                    Err(err) => {
+                        self.record_convert_error(key, err);
+                        return None;
+                    }
+                };
+                if let Some(cache) = &self.shared_render_cache {
+                    let mut cache = cache.lock().expect("could not lock shared render cache");
+                    cache.entries.insert(shared_key, converted.clone());
+                }
+                if let (Some(content_key), Some(renders)) = (content_key, &mut self.content_renders)
+                {
+                    renders.insert(content_key, converted.clone());
+                }
+                self.insert_render(key.clone(), converted);
+            }
+        } else {
+            *self.convert_hits.entry(counter).or_insert(0) += 1;
+            self.touch_render(&key);
+        }
+
+        // get value and convert to G
+        self.render_cache.get(&key).map(|a| a.downcast::<G>())
+    }
+
+    /// Get why the latest conversion of a source with the params failed
+    ///
+    /// Cleared once the source or recipe changes and the conversion runs again
+    pub fn convert_error<G: ConvertableRenderAsset>(
+        &self,
+        handle: &AssetHandle<G::SourceAsset>,
+        params: &G::Params,
+    ) -> Option<&ConvertError> {
+        let key = RenderKey::new::<G>(handle.clone_typed(), params);
+        self.convert_errors
+            .get(&key)
+            .filter(|(version, _)| *version == self.render_version(&key))
+            .map(|(_, err)| err)
+    }
+
+    // whether converting the current source and recipe already failed
+    fn convert_failed(&self, key: &RenderKey) -> bool {
+        self.convert_errors
+            .get(key)
+            .is_some_and(|(version, _)| *version == self.render_version(key))
+    }
+
+    fn record_convert_error(&mut self, key: RenderKey, err: ConvertError) {
+        let version = self.render_version(&key);
+        self.convert_errors.insert(key, (version, err));
+    }
+
+    fn render_version(&self, key: &RenderKey) -> (u64, u64) {
+        (
+            self.source_version(&key.source),
+            self.recipe_version(key.ty),
+        )
+    }
+
+    /// Convert a render asset and remember the params
+    ///
+    /// When the source reloads, poll_reload converts it again with the latest params so
+    /// the render asset is up to date before the next convert
+    pub fn convert_tracked<G: ConvertableRenderAsset>(
+        &mut self,
+        handle: AssetHandle<G::SourceAsset>,
+        params: &G::Params,
+    ) -> Option<ArcHandle<G>>
+    where
+        G::Params: Clone + Send + 'static,
+    {
+        self.track_convert::<G>(&handle, params, Self::reconvert_erased::<G>);
+        self.convert::<G>(handle, params)
+    }
+
+    fn track_convert<G: ConvertableRenderAsset>(
+        &mut self,
+        handle: &AssetHandle<G::SourceAsset>,
+        params: &G::Params,
+        reconvert: fn(&mut Assets, AssetHandle<DynAsset>, &(dyn Any + Send)),
+    ) where
+        G::Params: Clone + Send + 'static,
+    {
+        self.tracked_converts.insert(
+            (handle.clone_typed::<DynAsset>(), TypeId::of::<G>()),
+            TrackedConvert {
+                params: Box::new(params.clone()),
+                reconvert,
+            },
+        );
+    }
+
+    /// Get the params of the latest tracked conversion of a source
+    ///
+    /// Conversions through [`Assets::convert_tracked`] and [`Assets::convert_async`] are tracked
+    pub fn tracked_params<G: ConvertableRenderAsset>(
+        &self,
+        handle: &AssetHandle<G::SourceAsset>,
+    ) -> Option<&G::Params>
+    where
+        G::Params: 'static,
+    {
+        self.tracked_converts
+            .get(&(handle.clone_typed::<DynAsset>(), TypeId::of::<G>()))
+            .map(|tracked| {
+                tracked
+                    .params
+                    .downcast_ref::<G::Params>()
+                    .expect("could not downcast")
+            })
+    }
+
+    fn reconvert_erased<G: ConvertableRenderAsset>(
+        &mut self,
+        handle: AssetHandle<DynAsset>,
+        params: &(dyn Any + Send),
+    ) where
+        G::Params: 'static,
+    {
+        let params = params
+            .downcast_ref::<G::Params>()
+            .expect("could not downcast");
+        self.convert::<G>(handle.clone_typed(), params);
+    }
+
+    fn reconvert_async_erased<G: ConvertableRenderAsset>(
+        &mut self,
+        handle: AssetHandle<DynAsset>,
+        params: &(dyn Any + Send),
+    ) where
+        G::SourceAsset: Clone,
+        G::Params: Clone + Send + 'static,
+    {
+        let params = params
+            .downcast_ref::<G::Params>()
+            .expect("could not downcast");
+        self.convert_async::<G>(handle.clone_typed(), params);
+    }
+
+    // convert the tracked render assets of reloaded sources again
+    fn reconvert_tracked(&mut self, handles: &[AssetHandle<DynAsset>]) {
+        let keys: Vec<(AssetHandle<DynAsset>, TypeId)> = self
+            .tracked_converts
+            .keys()
+            .filter(|(source, _)| handles.contains(source))
+            .cloned()
+            .collect();
+        for key in keys {
+            let Some(tracked) = self.tracked_converts.remove(&key) else {
+                continue;
+            };
+            (tracked.reconvert)(self, key.0.clone(), tracked.params.as_ref());
+            self.tracked_converts.insert(key, tracked);
+        }
+    }
+
+    /// Convert a source through the previous stage into a render asset
+    ///
+    /// Both stages are cached in the render cache. Changing the source rebuilds both,
+    /// while a cached previous stage is reused by every stage built on it
+    pub fn convert_pipeline<G: ConvertStage>(
+        &mut self,
+        handle: AssetHandle<<G::Previous as ConvertableRenderAsset>::SourceAsset>,
+        previous_params: &<G::Previous as ConvertableRenderAsset>::Params,
+        params: &G::Params,
+    ) -> Option<ArcHandle<G>> {
+        let previous = self.convert::<G::Previous>(handle.clone(), previous_params)?;
+
+        // a rebuilt previous stage has a new id, which outdates the cached render asset
+        let key = RenderKey {
+            source: handle.clone_typed::<DynAsset>(),
+            ty: TypeId::of::<G>(),
+            params: (params.params_key(), previous.id()).params_key(),
+        };
+        self.discard_stale_render(&key);
+        if !self.render_cache.contains_key(&key) {
+            if self.convert_failed(&key) {
+                return None;
+            }
+            match G::convert(&previous, params) {
+                Ok(converted) => {
+                    self.insert_render(key.clone(), ArcHandle::new(converted).upcast())
+                }
+                Err(err) => {
+                    self.record_convert_error(key, err);
+                    return None;
+                }
+            }
+        } else {
+            self.touch_render(&key);
+        }
+
+        self.render_cache.get(&key).map(|a| a.downcast::<G>())
+    }
+
+    /// Share one render asset between sources loaded from files with identical content
+    ///
+    /// Saves memory when the same content is loaded under several paths. Files are hashed
+    /// on load already, so the cost is a lookup per conversion. Only applies to convert,
+    /// and only to sources loaded from a file and not mutated since. Sources loaded from
+    /// the same file with different import settings would wrongly share a render asset
+    pub fn set_render_content_dedup(&mut self, enabled: bool) {
+        self.content_renders = enabled.then(HashMap::new);
+    }
+
+    /// Check if every handle has a converted render asset
+    pub fn render_ready(&self, handles: &[AssetHandle<DynAsset>]) -> bool {
+        handles
+            .iter()
+            .all(|handle| self.render_cache.keys().any(|key| key.source == *handle))
+    }
+
+    /// Convert the handles which do not have a render asset with the params yet
+    pub fn warm_render<G: ConvertableRenderAsset>(
+        &mut self,
+        handles: &[AssetHandle<G::SourceAsset>],
+        params: &G::Params,
+    ) {
+        for handle in handles {
+            let key = RenderKey::new::<G>(handle.clone_typed(), params);
+            if !self.render_cache.contains_key(&key) {
+                self.convert::<G>(handle.clone(), params);
+            }
+        }
+    }
+
+    /// Convert a render asset on the worker pool
+    ///
+    /// Returns the render asset if it is already converted, otherwise schedules a
+    /// conversion which is made available by poll_converted.
+    /// Conversions of a source that changes while in flight are discarded.
+    /// The params are remembered, so a reload of the source schedules a conversion
+    /// right away
+    pub fn convert_async<G: ConvertableRenderAsset>(
+        &mut self,
+        handle: AssetHandle<G::SourceAsset>,
+        params: &G::Params,
+    ) -> Option<ArcHandle<G>>
+    where
+        G::SourceAsset: Clone,
+        G::Params: Clone + Send + 'static,
+    {
+        self.track_convert::<G>(&handle, params, Self::reconvert_async_erased::<G>);
+
+        let erased = handle.clone_typed::<DynAsset>();
+        let key = RenderKey::new::<G>(erased.clone(), params);
+        self.discard_stale_render(&key);
+        let counter = (erased.clone(), TypeId::of::<G>());
+        if let Some(render) = self.render_cache.get(&key) {
+            let render = render.downcast::<G>();
+            *self.convert_hits.entry(counter).or_insert(0) += 1;
+            self.touch_render(&key);
+            return Some(render);
+        }
+
+        // skip if an up to date conversion is already in flight or failed
+        let version = self.render_version(&key);
+        if self.convert_pending.get(&key) == Some(&version) || self.convert_failed(&key) {
+            return None;
+        }
+
+        let source = self.get(handle)?.clone();
+        let params = params.clone();
+        let convert_sender_clone = self.convert_sender.clone();
+        self.convert_pending.insert(key.clone(), version);
+        *self.convert_misses.entry(counter).or_insert(0) += 1;
+        self.load_workers.spawn(move || {
+            let converted = catch_unwind(AssertUnwindSafe(|| G::convert(&source, &params)))
+                .unwrap_or_else(|_| Err(ConvertError("convert panicked".to_string())))
+                .map(|converted| ArcHandle::new(converted).upcast());
+            convert_sender_clone
+                .send((key, version, converted))
+                .expect("could not send");
+        });
+
+        None
+    }
+
+    /// Get how many times convert actually ran for a source, None if it never ran
+    ///
+    /// Converting the same source twice without changing it should only count once
+    pub fn convert_count<G: ConvertableRenderAsset>(
+        &self,
+        handle: &AssetHandle<G::SourceAsset>,
+    ) -> Option<u64> {
+        self.convert_misses
+            .get(&(handle.clone_typed::<DynAsset>(), TypeId::of::<G>()))
+            .copied()
+    }
+
+    /// Get how many convert calls for a source were served from the render cache
+    pub fn convert_hit_count<G: ConvertableRenderAsset>(
+        &self,
+        handle: &AssetHandle<G::SourceAsset>,
+    ) -> Option<u64> {
+        self.convert_hits
+            .get(&(handle.clone_typed::<DynAsset>(), TypeId::of::<G>()))
+            .copied()
+    }
+
+    /// Get how many times the render asset of a source has been built
+    ///
+    /// Advances every time the render asset is rebuilt, e.g. after the source reloads
+    pub fn render_generation<G: ConvertableRenderAsset>(
+        &self,
+        handle: &AssetHandle<G::SourceAsset>,
+    ) -> Option<u64> {
+        self.render_generations
+            .get(&(handle.clone_typed::<DynAsset>(), TypeId::of::<G>()))
+            .copied()
+    }
+
+    /// Drop every render asset, e.g. after the gpu device was lost
+    ///
+    /// Source assets are kept and converted again on the next convert
+    pub fn clear_render_cache(&mut self) {
+        self.render_cache.clear();
+        self.render_sources.clear();
+        self.render_versions.clear();
+        self.render_last_used.clear();
+        self.convert_errors.clear();
+        if let Some(renders) = &mut self.content_renders {
+            renders.clear();
+        }
+
+        // outdate in flight conversions
+        let sources: HashSet<AssetHandle<DynAsset>> = self
+            .convert_pending
+            .keys()
+            .map(|key| key.source.clone())
+            .collect();
+        for source in sources {
+            *self.source_versions.entry(source).or_insert(0) += 1;
+        }
+    }
+
+    /// Iterate over all converted render assets of a type with their source handles
+    pub fn iter_render<G: RenderAsset + Send + Sync>(
+        &self,
+    ) -> impl Iterator<Item = (AssetHandle<DynAsset>, ArcHandle<G>)> + '_ {
+        self.render_cache
+            .iter()
+            .filter(|(key, _)| key.ty == TypeId::of::<G>())
+            .map(|(key, render)| (key.source.clone(), render.downcast::<G>()))
+    }
+
+    /// Get the render asset cached for the same source and params as a render asset
+    ///
+    /// Returns None once the render asset left the render cache, e.g. because the source
+    /// changed, and convert has to run again
+    pub fn refresh_render<G: ConvertableRenderAsset>(
+        &self,
+        render: &ArcHandle<G>,
+    ) -> Option<ArcHandle<G>> {
+        let key = self.render_sources.get(&render.id())?;
+        self.render_cache.get(key).map(|a| a.downcast::<G>())
+    }
+
+    fn insert_render(&mut self, key: RenderKey, render: DynRenderAsset) {
+        let version = (
+            self.source_version(&key.source),
+            self.recipe_version(key.ty),
+        );
+        self.render_versions.insert(key.clone(), version);
+        self.render_sources.insert(render.id(), key.clone());
+        *self
+            .render_generations
+            .entry((key.source.clone(), key.ty))
+            .or_insert(0) += 1;
+        self.touch_render(&key);
+        if self.render_cache.insert(key, render).is_some() {
+            self.prune_render_sources();
+        }
+        self.evict_renders();
+    }
+
+    // forget render ids whose render asset is no longer in the render cache
+    fn prune_render_sources(&mut self) {
+        let cache = &self.render_cache;
+        self.render_sources
+            .retain(|id, key| cache.get(key).is_some_and(|render| render.id() == *id));
+    }
+
+    fn touch_render(&mut self, key: &RenderKey) {
+        if self.render_limit.is_some() {
+            self.render_clock += 1;
+            self.render_last_used.insert(key.clone(), self.render_clock);
+        }
+    }
+
+    // drop the least recently used render assets above the render cache limit
+    fn evict_renders(&mut self) {
+        let Some(limit) = self.render_limit else {
+            return;
+        };
+        let cache = &self.render_cache;
+        self.render_last_used
+            .retain(|key, _| cache.contains_key(key));
+
+        while self.render_cache.len() > limit {
+            let Some(oldest) = self
+                .render_cache
+                .keys()
+                .min_by_key(|key| self.render_last_used.get(*key).copied().unwrap_or(0))
+                .cloned()
+            else {
+                break;
+            };
+            self.render_cache.remove(&oldest);
+            self.render_versions.remove(&oldest);
+            self.render_last_used.remove(&oldest);
+            self.render_sources.retain(|_, key| *key != oldest);
+        }
+    }
+
+    /// Invalidate every render asset of a type, e.g. after changing how it is converted
+    ///
+    /// The render assets are converted again on the next convert
+    pub fn bump_recipe<G: ConvertableRenderAsset>(&mut self) {
+        *self.recipe_versions.entry(TypeId::of::<G>()).or_insert(0) += 1;
+        self.render_cache
+            .retain(|key, _| key.ty != TypeId::of::<G>());
+        self.prune_render_sources();
+    }
+
+    fn recipe_version(&self, ty_id: TypeId) -> u64 {
+        self.recipe_versions.get(&ty_id).copied().unwrap_or(0)
+    }
+
+    fn source_version(&self, handle: &AssetHandle<DynAsset>) -> u64 {
+        self.source_versions.get(handle).copied().unwrap_or(0)
+    }
+
+    // guard against render assets built from an older source or recipe
+    fn discard_stale_render(&mut self, key: &RenderKey) {
+        let current = (
+            self.source_version(&key.source),
+            self.recipe_version(key.ty),
+        );
+        if self
+            .render_versions
+            .get(key)
+            .is_some_and(|version| *version != current)
+        {
+            self.render_cache.remove(key);
+            self.render_versions.remove(key);
+            self.prune_render_sources();
+        }
+    }
+
+    // invalidate render cache and outdate in flight conversions
+    fn invalidate_render(&mut self, handle: &AssetHandle<DynAsset>) {
+        self.render_cache.retain(|key, _| key.source != *handle);
+        self.prune_render_sources();
+        *self.source_versions.entry(handle.clone()).or_insert(0) += 1;
+
+        // drop identical content render assets nothing else uses
+        if let Some(renders) = &mut self.content_renders {
+            renders.retain(|_, render| Arc::strong_count(&render.handle) > 1);
+        }
+    }
+
+    // drop shared render assets of the file and of this instance's copy of the source,
+    // other instances convert their content again on the next convert
+    fn invalidate_shared_render(&mut self, handle: &AssetHandle<DynAsset>) {
+        let Some(cache) = &self.shared_render_cache else {
+            return;
+        };
+        let path = self.asset_paths.get(handle);
+        let own = SourceKey::Instance(self.instance, handle.id);
+        let mut cache = cache.lock().expect("could not lock shared render cache");
+        cache.entries.retain(|(source, _, _), _| match source {
+            SourceKey::Content(source_path, _) => Some(source_path) != path,
+            source => *source != own,
+        });
+    }
+
+    // sources only share render assets while their content matches the file
+    fn source_key(&self, handle: &AssetHandle<DynAsset>) -> SourceKey {
+        match (
+            self.asset_paths.get(handle),
+            self.content_hashes.get(handle),
+        ) {
+            (Some(path), Some(hash)) => SourceKey::Content(path.clone(), *hash),
+            _ => SourceKey::Instance(self.instance, handle.id),
+        }
+    }
+
+    //
+    // Polling
+    //
+
+    /// Deliver async loads through poll_loaded in the order they were scheduled
+    ///
+    /// Only affects loads scheduled after enabling. A slow load holds back every
+    /// later load until it finishes, even if those already completed.
+    /// Disabling delivers all held back loads on the next poll_loaded
+    pub fn set_ordered_delivery(&mut self, ordered: bool) {
+        if ordered && !self.ordered_delivery {
+            self.ordered_next = self.load_sequence;
+        }
+        self.ordered_delivery = ordered;
+    }
+
+    // check if any files completed loading and update cache and invalidate render cache
+    pub fn poll_loaded(&mut self) {
+        let loaded: Vec<LoadedAsset> = self.load_receiver.try_iter().collect();
+        self.deliver_loaded(loaded);
+    }
+
+    /// Wait until an async load is delivered or the timeout passes and get its state
+    ///
+    /// Other loads completing while waiting are delivered as by poll_loaded
+    pub fn block_until_loaded<T: Asset>(
+        &mut self,
+        handle: AssetHandle<T>,
+        timeout: Option<Duration>,
+    ) -> AssetState {
+        let erased = handle.clone_typed::<DynAsset>();
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        self.poll_loaded();
+        while self.load_pending.contains(&erased) {
+            let received = match deadline {
+                Some(deadline) => self
+                    .load_receiver
+                    .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                    .ok(),
+                None => self.load_receiver.recv().ok(),
+            };
+            let Some(loaded) = received else {
+                break;
+            };
+            let mut loaded = vec![loaded];
+            loaded.extend(self.load_receiver.try_iter());
+            self.deliver_loaded(loaded);
+        }
+        self.asset_state(handle)
+    }
+
+    fn deliver_loaded(&mut self, loaded: Vec<LoadedAsset>) {
+        for loaded in loaded {
+            match loaded.sequence {
+                Some(sequence) if self.ordered_delivery && sequence >= self.ordered_next => {
+                    // held back loads must not block the load they wait for
+                    let mut loaded = loaded;
+                    loaded.bytes = None;
+                    self.ordered_buffer.insert(sequence, loaded);
+                }
+                _ => self.insert_loaded(loaded),
+            }
+        }
+
+        if self.ordered_delivery {
+            // release buffered loads that are next in line
+            while let Some(loaded) = self.ordered_buffer.remove(&self.ordered_next) {
+                self.ordered_next += 1;
+                self.insert_loaded(loaded);
+            }
+        } else {
+            for (_, loaded) in std::mem::take(&mut self.ordered_buffer) {
+                self.insert_loaded(loaded);
+            }
+        }
+    }
+
+    fn insert_loaded(&mut self, loaded: LoadedAsset) {
+        let handle = loaded.handle;
+        self.load_pending.remove(&handle);
+        self.load_cancel_flags.remove(&handle);
+        if self.load_cancelled.remove(&handle) {
+            self.load_callbacks.remove(&handle);
+            return;
+        }
+
+        // an explicitly inserted asset took the id while loading
+        if self.explicit_ids.contains(&handle.id) {
+            let colliding: Vec<AssetHandle<DynAsset>> = self
+                .cache
+                .keys()
+                .filter(|cached| cached.id == handle.id)
+                .cloned()
+                .collect();
+            if !colliding.is_empty() {
+                match self.id_collision_policy {
+                    IdCollisionPolicy::LastWriterWins => {
+                        for cached in colliding {
+                            self.cache.remove(&cached);
+                            self.invalidate_render(&cached);
+                        }
+                    }
+                    IdCollisionPolicy::Reject => return,
+                }
+            }
+        }
+
+        let asset = match loaded.asset {
+            Ok(asset) => asset,
+            Err(err) => {
+                let err = match self.asset_paths.get(&handle) {
+                    Some(path) => err.at_path(path),
+                    None => err,
+                };
+                self.load_callbacks.remove(&handle);
+                self.load_errors.insert(handle, err);
+                return;
+            }
+        };
+
+        match loaded.content_hash {
+            Some(hash) => self.content_hashes.insert(handle.clone(), hash),
+            None => self.content_hashes.remove(&handle),
+        };
+        match loaded.modified {
+            Some(modified) => self.load_mtimes.insert(handle.clone(), modified),
+            None => self.load_mtimes.remove(&handle),
+        };
+        self.load_errors.remove(&handle);
+        self.changes.loaded.insert(handle.clone());
+        self.cache.insert(handle.clone(), Arc::from(asset));
+        self.reset_appended(&handle);
+        self.invalidate_render(&handle);
+
+        for callback in self.load_callbacks.remove(&handle).unwrap_or_default() {
+            callback(self.cache[&handle].as_ref());
+        }
+    }
+
+    // check if any async conversions completed and update render cache
+    pub fn poll_converted(&mut self) {
+        let converted: Vec<ConvertedAsset> = self.convert_receiver.try_iter().collect();
+        for (key, version, render) in converted {
+            if self.convert_pending.get(&key) == Some(&version) {
+                self.convert_pending.remove(&key);
+            }
+
+            // discard conversions of outdated sources or recipes
+            if self.render_version(&key) != version || self.render_cache.contains_key(&key) {
+                continue;
+            }
+            match render {
+                Ok(render) => self.insert_render(key, render),
+                Err(err) => self.record_convert_error(key, err),
+            }
+        }
+    }
+
+    /// Write dirty assets grouped by directory and sync each directory once afterwards
+    ///
+    /// Speeds up saving many small assets, the files themselves are not synced
+    pub fn set_write_batch(&mut self, batch: bool) {
+        self.write_batch = batch;
+    }
+
+    /// Write every asset registered for writing, whether it changed or not
+    pub fn save_all(&mut self) {
+        let handles: Vec<AssetHandle<DynAsset>> = self
+            .load_handles
+            .keys()
+            .filter(|handle| !self.frozen.contains(*handle))
+            .cloned()
+            .collect();
+        self.load_dirty.extend(handles);
+        self.poll_write();
+    }
+
+    // check if any files are scheduled for writing to disk, assets which are shared and can
+    // not be copied on write stay scheduled and report a write error until they are unshared
+    pub fn poll_write(&mut self) {
+        let mut dirty: Vec<AssetHandle<DynAsset>> = self.load_dirty.drain().collect();
+        if self.write_batch {
+            dirty.sort_by(|a, b| {
+                let parent = |handle| self.load_handles.get(handle).and_then(|path| path.parent());
+                parent(a).cmp(&parent(b))
+            });
+        }
+
+        let mut written_dirs = HashSet::new();
+        let mut still_shared = Vec::new();
+        for handle in dirty {
+            if let Some(path) = self.load_handles.get(&handle).cloned() {
+                if let Some(dir) = path.parent().filter(|_| self.write_batch) {
+                    written_dirs.insert(dir.to_path_buf());
+                }
+
+                // append new entries only
+                if let Some(written) = self.append_written.get_mut(&handle) {
+                    let Some(asset) = self.cache.get(&handle) else {
+                        continue;
+                    };
+                    let append_type = self.append_types[&handle.ty_id];
+                    match fs::OpenOptions::new().create(true).append(true).open(&path) {
+                        Ok(mut file) => {
+                            (append_type.append)(asset.as_ref(), *written, &mut file);
+                            *written = (append_type.entries)(asset.as_ref());
+                            self.ignore_own_write(&handle, &path);
+                            self.write_errors.remove(&handle);
+                            self.changes.written.insert(handle.clone());
+                        }
+                        Err(err) => {
+                            self.write_errors.insert(handle.clone(), err);
+                        }
+                    }
+                    self.finish_write(&handle);
+                    continue;
+                }
+
+                // shared assets are written from a copy, without copy on write the write
+                // waits until the asset is no longer shared
+                if !self.unshare(&handle) {
+                    if self.cache.contains_key(&handle) {
+                        self.write_errors
+                            .insert(handle.clone(), std::io::Error::other(AssetError::Shared));
+                        still_shared.push(handle);
+                    }
+                    continue;
+                }
+                let asset = self.cache.get_mut(&handle).and_then(Arc::get_mut);
+
+                // write if loaded
+                if let Some(asset) = asset {
+                    let write_fn = self
+                        .write_functions
+                        .get(&handle.ty_id)
+                        .expect("could not get write fn");
+
+                    write_fn(asset, &path);
+                    self.ignore_own_write(&handle, &path);
+                    self.write_errors.remove(&handle);
+                    self.changes.written.insert(handle.clone());
+                    self.finish_write(&handle);
+                }
+            }
+        }
+
+        self.load_dirty.extend(still_shared);
+
+        // directories can not be opened for syncing on every platform
+        for dir in written_dirs {
+            let _ = fs::File::open(&dir).and_then(|dir| dir.sync_all());
+        }
+    }
+
+    /// Get the assets loaded, reloaded or written since the previous call
+    pub fn changed(&mut self) -> ChangeSet {
+        std::mem::take(&mut self.changes)
+    }
+
+    // checks if any files changed and spawns a thread which reloads the data
+    pub fn poll_reload(&mut self) {
+        let forced = std::mem::take(
+            &mut *self
+                .forced_reloads
+                .lock()
+                .expect("could not lock forced reloads"),
+        );
+        let mut paths: Vec<PathBuf> = Vec::new();
+        for path in self.reload_receiver.try_iter() {
+            // touches and editors saving identical content do not reload
+            if let Some(hash) = content_hash(&path)
+                && self.watched_hashes.insert(path.clone(), hash) == Some(hash)
+                && !forced.contains(&path)
+            {
+                self.reload_stats.unchanged += 1;
+                continue;
+            }
+            paths.push(path);
+        }
+
+        // watched directories reload when any file inside them changes
+        let mut reloads: Vec<(PathBuf, AssetHandle<DynAsset>)> = Vec::new();
+        for path in paths.iter() {
+            let mut watched_by_any = false;
+            for watched in path.ancestors() {
+                if self.dependents.contains_key(watched) {
+                    watched_by_any = true;
+                }
+                // sidecar changes reload the asset they configure
+                let load_path = self
+                    .sidecars
+                    .get(watched)
+                    .cloned()
+                    .unwrap_or(watched.to_path_buf());
+                for handle in self.reload_handles.get(watched).into_iter().flatten() {
+                    watched_by_any = true;
+                    let reload = (load_path.clone(), handle.clone());
+                    if !reloads.contains(&reload) {
+                        reloads.push(reload);
+                    }
+                }
+            }
+            if !watched_by_any {
+                self.reload_stats.dropped += 1;
+            }
+        }
+        self.add_dependent_reloads(&paths, &mut reloads);
+        self.reload_stats.coalesced_reloads += reloads.len() as u64;
+
+        // let the interceptor decide per path
+        if let Some(interceptor) = &self.reload_interceptor {
+            let mut paths: Vec<PathBuf> = Vec::new();
+            for (path, _) in reloads.iter() {
+                if !paths.contains(path) {
+                    paths.push(path.clone());
+                }
+            }
+
+            let mut held_back = HashSet::new();
+            for path in paths {
+                let handles: Vec<AssetHandle<DynAsset>> = reloads
+                    .iter()
+                    .filter(|(reload_path, _)| *reload_path == path)
+                    .map(|(_, handle)| handle.clone())
+                    .collect();
+                let context = ReloadContext {
+                    path: &path,
+                    handles: &handles,
+                    cache: &self.cache,
+                };
+                match interceptor(&context) {
+                    ReloadDecision::Apply => {}
+                    ReloadDecision::Skip => {
+                        held_back.insert(path);
+                    }
+                    ReloadDecision::Defer => {
+                        // forget the content so the deferred event is not seen as unchanged
+                        self.watched_hashes.remove(&path);
+                        self.reload_sender
+                            .send(path.clone())
+                            .expect("could not send path");
+                        held_back.insert(path);
+                    }
+                }
+            }
+            reloads.retain(|(path, _)| !held_back.contains(path));
+        }
+
+        let mut reloaded = Vec::new();
+        let mut labeled_loads = HashMap::new();
+        for (path, handle) in reloads {
+            // create/overwrite current value
+            let modified = modified_time(&path);
+            let loaded = with_context(&self.load_context(), || {
+                if self.labels.contains_key(&handle) {
+                    self.reload_labeled(&path, &handle, &mut labeled_loads)
+                } else {
+                    let loader_fn = self
+                        .reload_functions
+                        .get(&handle)
+                        .expect("could not get loader fn");
+                    loader_fn(&path)
+                }
+            });
+            let asset = match loaded {
+                Ok(asset) => asset,
+                Err(err) => {
+                    // keep the previous version until the file loads again
+                    self.load_errors.insert(handle, err.at_path(&path));
+                    continue;
+                }
+            };
+            self.load_errors.remove(&handle);
+            self.changes.reloaded.insert(handle.clone());
+            self.cache.insert(handle.clone(), Arc::from(asset));
+            self.reset_appended(&handle);
+            match content_hash(&path) {
+                Some(hash) => self.content_hashes.insert(handle.clone(), hash),
+                None => self.content_hashes.remove(&handle),
+            };
+            match modified {
+                Some(modified) => self.load_mtimes.insert(handle.clone(), modified),
+                None => self.load_mtimes.remove(&handle),
+            };
+
+            // invalidate render cache
+            self.invalidate_render(&handle);
+            self.invalidate_shared_render(&handle);
+            reloaded.push(handle);
+        }
+
+        self.reconvert_tracked(&reloaded);
+    }
+
+    /// Poll everything in the order a frame needs
+    ///
+    /// Call once at the start of a frame, before any convert. Loads and reloads are
+    /// applied first so the conversions that follow never see an outdated source
+    pub fn tick(&mut self) {
+        self.poll_loaded();
+        self.poll_reload();
+        self.poll_write();
+        self.poll_converted();
+    }
+
+    /// Process everything pending until nothing is in flight
+    ///
+    /// Blocks until all async loads and conversions have completed and been applied.
+    /// Loads, reloads and writes that are scheduled while flushing are processed as well
+    pub fn flush(&mut self) {
+        loop {
+            self.poll_loaded();
+            self.poll_reload();
+            self.poll_converted();
+            self.poll_write();
+
+            if self.load_pending.is_empty() && self.convert_pending.is_empty() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Deliver completed loads of the handles until they are all loaded or the deadline passes
+    ///
+    /// Returns how many of the handles are still loading
+    pub fn load_batch_until(
+        &mut self,
+        handles: &[AssetHandle<DynAsset>],
+        deadline: Instant,
+    ) -> usize {
+        loop {
+            self.poll_loaded();
+
+            let remaining = handles
+                .iter()
+                .filter(|handle| self.load_pending.contains(*handle))
+                .count();
+            let now = Instant::now();
+            if remaining == 0 || now >= deadline {
+                return remaining;
+            }
+            std::thread::sleep((deadline - now).min(Duration::from_millis(1)));
+        }
+    }
+
+    /// Load files in the background as a batch
+    ///
+    /// The progress of the batch is available through [`Assets::batch_progress`]
+    pub fn load_batch<T: Asset + LoadableAsset + WriteableAsset>(
+        &mut self,
+        paths: &[&Path],
+        watch: bool,
+        write: bool,
+    ) -> (Vec<AssetHandle<T>>, BatchHandle) {
+        let options = LoadOptions {
+            sync: false,
+            watch,
+            write,
+        };
+        let handles: Vec<AssetHandle<T>> = paths
+            .iter()
+            .map(|path| self.load_with_options(path, options))
+            .collect();
+
+        let batch = BatchHandle(self.next_batch);
+        self.next_batch += 1;
+        self.batches.insert(
+            batch,
+            handles
+                .iter()
+                .map(|handle| handle.clone_typed::<DynAsset>())
+                .collect(),
+        );
+        (handles, batch)
+    }
+
+    /// Get how many loads of a batch are done and how many it has in total
+    ///
+    /// Loads count as done once delivered by poll_loaded, failed loads included
+    pub fn batch_progress(&self, batch: BatchHandle) -> (usize, usize) {
+        let Some(handles) = self.batches.get(&batch) else {
+            return (0, 0);
+        };
+        let done = handles
+            .iter()
+            .filter(|handle| !self.load_pending.contains(*handle))
+            .count();
+        (done, handles.len())
+    }
+
+    /// Stop tracking the progress of a batch, the assets stay loaded
+    pub fn forget_batch(&mut self, batch: BatchHandle) {
+        self.batches.remove(&batch);
+    }
+
+    /// Decide for every pending reload whether it is applied, skipped or deferred
+    ///
+    /// Called in poll_reload before the loader runs. Deferred reloads are queued
+    /// again and passed to the interceptor on the next poll_reload
+    pub fn set_reload_interceptor(
+        &mut self,
+        interceptor: impl Fn(&ReloadContext) -> ReloadDecision + Send + 'static,
+    ) {
+        self.reload_interceptor = Some(Box::new(interceptor));
+    }
+
+    /// Get counters of watcher events and the reloads they caused
+    pub fn watcher_stats(&self) -> WatcherStats {
+        WatcherStats {
+            raw_events: self.reload_raw_events.load(SeqCst),
+            watched_paths: self.reload_handles.len(),
+            ..self.reload_stats
+        }
+    }
+
+    /// Include the size of assets of a type in [`Assets::stats`]
+    pub fn register_measurable<T: MeasurableAsset>(&mut self) {
+        self.size_functions
+            .insert(TypeId::of::<T>(), Self::byte_size_erased::<T>);
+    }
+
+    fn byte_size_erased<T: MeasurableAsset>(asset: &dyn Asset) -> usize {
+        asset
+            .as_any()
+            .downcast_ref::<T>()
+            .expect("could not downcast")
+            .byte_size()
+    }
+
+    /// Get the number of assets held, e.g. for a debug overlay
+    pub fn stats(&self) -> AssetStats {
+        // assets shared by several handles are counted once
+        let mut measured = HashSet::new();
+        let mut bytes = 0;
+        for (handle, asset) in self.cache.iter() {
+            let Some(size_fn) = self.size_functions.get(&handle.ty_id) else {
+                continue;
+            };
+            if measured.insert(Arc::as_ptr(asset) as *const ()) {
+                bytes += size_fn(asset.as_ref());
+            }
+        }
+
+        AssetStats {
+            assets: self.cache.len(),
+            render_assets: self.render_cache.len(),
+            watched_paths: self.reload_handles.len(),
+            dirty: self.load_dirty.len(),
+            pending: self.load_pending.len(),
+            bytes,
+        }
+    }
+
+    /// Reload the assets watching a path, even if its content did not change
+    pub fn force_reload(&self, path: PathBuf) {
+        self.forced_reloads
+            .lock()
+            .expect("could not lock forced reloads")
+            .insert(path.clone());
+        self.reload_sender.send(path).expect("could not send path");
+    }
+}
+
+// time file changes have to settle before they are reloaded
+const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(100);
+
+// one load worker per available core
+fn default_workers() -> usize {
+    std::thread::available_parallelism().map_or(4, |n| n.get())
+}
+
+// run a loader on a worker, a panic fails the load instead of leaving it pending
+fn catch_load<T>(load: impl FnOnce() -> Result<T, AssetLoadError>) -> Result<T, AssetLoadError> {
+    catch_unwind(AssertUnwindSafe(load)).unwrap_or(Err(AssetLoadError::Panicked))
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+// fnv-1a, the same bytes always give the same hash while DefaultHasher may change
+// between rust versions
+fn stable_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// hash the content of a file, None if it can not be read
+fn content_hash(path: &Path) -> Option<u64> {
+    let bytes = fs::read(path).ok()?;
+    let mut hasher = std::hash::DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+impl dyn Asset {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+static NEXT_INSTANCE: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug)]
+pub struct ArcHandle<T: ?Sized + 'static> {
+    pub handle: Arc<T>,
+    id: u64,
+}
+
+impl<T: 'static> ArcHandle<T> {
+    pub fn new(handle: T) -> Self {
+        ArcHandle {
+            handle: Arc::new(handle),
+            id: NEXT_ID.fetch_add(1, SeqCst),
+        }
+    }
+}
+
+impl<T: ?Sized + 'static> ArcHandle<T> {
+    #[inline]
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl<T: ?Sized + 'static> Clone for ArcHandle<T> {
+    fn clone(&self) -> Self {
+        ArcHandle {
+            handle: Arc::clone(&self.handle),
+            id: self.id,
+        }
+    }
+}
+
+impl<T: 'static> PartialEq for ArcHandle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T: 'static> Eq for ArcHandle<T> {}
+
+impl<T: 'static> std::hash::Hash for ArcHandle<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl<T: 'static> std::ops::Deref for ArcHandle<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.handle.as_ref()
+    }
+}
+
+impl<T: 'static> AsRef<T> for ArcHandle<T> {
+    fn as_ref(&self) -> &T {
+        self.handle.as_ref()
+    }
+}
+
+// any stuff
+
+impl<T: Any + Send + Sync + 'static> ArcHandle<T> {
+    pub fn upcast(self) -> ArcHandle<dyn Any + Send + Sync> {
+        ArcHandle {
+            handle: self.handle as Arc<dyn Any + Send + Sync>,
+            id: self.id,
+        }
+    }
+}
+impl ArcHandle<dyn Any + Sync + Send> {
+    fn downcast<G: Send + Sync>(&self) -> ArcHandle<G> {
+        ArcHandle {
+            handle: self
+                .handle
+                .clone()
+                .downcast::<G>()
+                .expect("could not downcast"),
+            id: self.id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    // asset holding the content of a text file
+    #[derive(Debug, Clone, PartialEq)]
+    struct Text(String);
+
+    impl Asset for Text {}
+
+    impl LoadableAsset for Text {
+        fn load(path: &Path) -> Result<Self, AssetLoadError> {
+            Ok(Text(read_file_to_string(path)?))
+        }
+    }
+
+    impl LoadableFromBytes for Text {
+        fn from_bytes(bytes: &[u8]) -> Result<Self, AssetLoadError> {
+            String::from_utf8(bytes.to_vec())
+                .map(Text)
+                .map_err(|err| AssetLoadError::Parse(err.to_string()))
+        }
+    }
+
+    impl WriteableAsset for Text {
+        fn write(&mut self, path: &Path) {
+            fs::write(path, &self.0).expect("could not write text");
+        }
+    }
+
+    // render asset converted from text
+    #[derive(Debug)]
+    struct Upper(String);
+
+    impl RenderAsset for Upper {}
+
+    impl ConvertableRenderAsset for Upper {
+        type SourceAsset = Text;
+        type Params = ();
+
+        fn convert(source: &Text, _params: &()) -> Result<Self, ConvertError> {
+            Ok(Upper(source.0.to_uppercase()))
+        }
+    }
+
+    // second render asset of text
+    struct Length(usize);
+
+    impl RenderAsset for Length {}
+
+    impl ConvertableRenderAsset for Length {
+        type SourceAsset = Text;
+        type Params = ();
+
+        fn convert(source: &Text, _params: &()) -> Result<Self, ConvertError> {
+            Ok(Length(source.0.len()))
+        }
+    }
+
+    // empty directory only used by one test
+    fn temp_dir() -> PathBuf {
+        static NEXT_DIR: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "assets-test-{}-{}",
+            std::process::id(),
+            NEXT_DIR.fetch_add(1, SeqCst)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("could not create temp dir");
+        dir
+    }
+
+    #[test]
+    fn write_as_writes_to_several_paths() {
+        let dir = temp_dir();
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        let mut assets = Assets::new();
+        let handle = assets.load_write::<Text>(&dir.join("a.txt"), true);
+        assets.get_mut(handle.clone()).unwrap().0 = "changed".to_string();
+
+        assets.write_as(&handle, &dir.join("b.txt")).unwrap();
+        assets.write_as(&handle, &dir.join("c.txt")).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("b.txt")).unwrap(), "changed");
+        assert_eq!(fs::read_to_string(dir.join("c.txt")).unwrap(), "changed");
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "a");
+
+        // the registered path is still written by poll_write
+        assets.poll_write();
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "changed");
+    }
+
+    #[test]
+    fn write_to_keeps_the_reload_and_write_paths() {
+        let dir = temp_dir();
+        let path = dir.join("a.txt");
+        fs::write(&path, "a").unwrap();
+        let mut assets = Assets::new();
+        let handle = assets.load_watch_write::<Text>(&path, true);
+        assets.get_mut(handle.clone()).unwrap().0 = "changed".to_string();
+
+        assets.write_to(handle.clone(), &dir.join("b.txt"));
+        assert_eq!(fs::read_to_string(dir.join("b.txt")).unwrap(), "changed");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a");
+        assert!(assets.write_error(&handle).is_none());
+
+        // the registered path is still reloaded and written
+        fs::write(&path, "reloaded").unwrap();
+        assets.force_reload(fs::canonicalize(&path).unwrap());
+        assets.poll_reload();
+        assert_eq!(assets.get(handle.clone()).unwrap().0, "reloaded");
+        assets.get_mut(handle.clone()).unwrap().0 = "written".to_string();
+        assets.poll_write();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "written");
+        assert_eq!(fs::read_to_string(dir.join("b.txt")).unwrap(), "changed");
+    }
+
+    #[test]
+    fn write_to_a_watched_path_does_not_reload_the_asset() {
+        let dir = temp_dir();
+        let path = dir.join("a.txt");
+        fs::write(&path, "a").unwrap();
+        let mut assets = Assets::new();
+        let handle = assets.load_watch::<Text>(&path, true);
+        assets.changed();
+
+        assets.get_mut(handle.clone()).unwrap().0 = "changed".to_string();
+        assets.write_to(handle.clone(), &path);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "changed");
+
+        // the change event of the own write
+        let canonical = fs::canonicalize(&path).unwrap();
+        assets.reload_sender.send(canonical).unwrap();
+        assets.poll_reload();
+        assert!(!assets.changed().reloaded.contains(&handle.erase()));
+    }
+
+    #[test]
+    fn write_to_reports_failures_as_write_errors() {
+        let path = temp_dir().join("a.txt");
+        let mut assets = Assets::new();
+        let handle = assets.insert(Text("a".to_string()));
+        let arc = assets.get_arc(&handle).unwrap();
+
+        // shared without copy on write
+        assets.write_to(handle.clone(), &path);
+        assert!(assets.write_error(&handle).is_some());
+        assert!(!path.exists());
+
+        assets.register_copy_on_write::<Text>();
+        assets.write_to(handle.clone(), &path);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a");
+        assert_eq!(arc.0, "a");
+    }
+
+    #[test]
+    fn set_write_path_redirects_later_writes() {
+        let dir = temp_dir();
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        let mut assets = Assets::new();
+        let handle = assets.load_write::<Text>(&dir.join("a.txt"), true);
+        assets.set_write_path(handle.clone(), &dir.join("saved.txt"));
+
+        assets.get_mut(handle.clone()).unwrap().0 = "saved".to_string();
+        assets.poll_write();
+        assert_eq!(fs::read_to_string(dir.join("saved.txt")).unwrap(), "saved");
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "a");
+    }
+
+    #[test]
+    fn singleton_returns_the_loaded_asset_without_a_handle() {
+        let dir = temp_dir();
+        fs::write(dir.join("config.txt"), "fullscreen").unwrap();
+        let mut assets = Assets::new();
+        let handle = assets.load_singleton::<Text>(&dir.join("config.txt"), true);
+
+        assert_eq!(
+            assets.singleton::<Text>(),
+            Some(&Text("fullscreen".to_string()))
+        );
+        assert_eq!(assets.singleton_handle::<Text>(), Some(handle));
+    }
+
+    #[test]
+    fn set_singleton_replaces_the_canonical_asset() {
+        let mut assets = Assets::new();
+        assets.set_singleton(Text("first".to_string()));
+        let second = assets.set_singleton(Text("second".to_string()));
+        assert_eq!(
+            assets.singleton::<Text>(),
+            Some(&Text("second".to_string()))
+        );
+        assert_eq!(assets.singleton_handle::<Text>(), Some(second));
+    }
+
+    #[test]
+    fn convert_async_delivers_through_poll_converted() {
+        let mut assets = Assets::new();
+        let handle = assets.insert(Text("shader".to_string()));
+        assert!(assets.convert_async::<Upper>(handle.clone(), &()).is_none());
+
+        assets.flush();
+        let render = assets.convert_async::<Upper>(handle.clone(), &()).unwrap();
+        assert_eq!(render.0, "SHADER");
+        assert_eq!(assets.convert_count::<Upper>(&handle), Some(1));
+    }
+
+    #[test]
+    fn convert_async_discards_results_of_changed_sources() {
+        let mut assets = Assets::new();
+        let handle = assets.insert(Text("old".to_string()));
+        assert!(assets.convert_async::<Upper>(handle.clone(), &()).is_none());
+        assets.get_mut(handle.clone()).unwrap().0 = "new".to_string();
+
+        assets.flush();
+        assert!(assets.convert_async::<Upper>(handle.clone(), &()).is_none());
+        assets.flush();
+        let render = assets.convert_async::<Upper>(handle.clone(), &()).unwrap();
+        assert_eq!(render.0, "NEW");
+    }
+
+    #[test]
+    fn preprocessor_substitutes_tokens_before_parsing() {
+        let dir = temp_dir();
+        let path = dir.join("shader.txt");
+        fs::write(&path, "color = $RED").unwrap();
+        let mut assets = Assets::new();
+        assets.set_preprocessor(|_, bytes| {
+            String::from_utf8(bytes)
+                .unwrap()
+                .replace("$RED", "#ff0000")
+                .into_bytes()
+        });
+
+        let handle = assets.load_source_watch::<Text>(&path, true);
+        assert_eq!(assets.get(handle.clone()).unwrap().0, "color = #ff0000");
+
+        // reloads are preprocessed as well
+        fs::write(&path, "background = $RED").unwrap();
+        assets.force_reload(fs::canonicalize(&path).unwrap());
+        assets.poll_reload();
+        assert_eq!(assets.get(handle).unwrap().0, "background = #ff0000");
+    }
+
+    #[test]
+    fn preprocessor_applies_to_plain_loads_and_reloads() {
+        let dir = temp_dir();
+        let path = dir.join("shader.txt");
+        fs::write(&path, "color = $RED").unwrap();
+        let mut assets = Assets::new();
+        assets.set_preprocessor(|_, bytes| {
+            String::from_utf8(bytes)
+                .unwrap()
+                .replace("$RED", "#ff0000")
+                .into_bytes()
+        });
+
+        let sync = assets.load_watch::<Text>(&path, true);
+        assert_eq!(assets.get(sync.clone()).unwrap().0, "color = #ff0000");
+        let other = dir.join("other.txt");
+        fs::write(&other, "$RED").unwrap();
+        let background = assets.load::<Text>(&other, false);
+        assets.flush();
+        assert_eq!(assets.get(background).unwrap().0, "#ff0000");
+
+        fs::write(&path, "background = $RED").unwrap();
+        assets.force_reload(fs::canonicalize(&path).unwrap());
+        assets.poll_reload();
+        assert_eq!(assets.get(sync).unwrap().0, "background = #ff0000");
+    }
+
+    #[test]
+    fn render_generation_advances_on_reload() {
+        let dir = temp_dir();
+        let path = dir.join("shader.txt");
+        fs::write(&path, "v1").unwrap();
+        let mut assets = Assets::new();
+        let handle = assets.load_watch::<Text>(&path, true);
+        assert_eq!(assets.render_generation::<Upper>(&handle), None);
+
+        assets.convert::<Upper>(handle.clone(), &()).unwrap();
+        assets.convert::<Upper>(handle.clone(), &()).unwrap();
+        assert_eq!(assets.render_generation::<Upper>(&handle), Some(1));
+
+        fs::write(&path, "v2").unwrap();
+        assets.force_reload(fs::canonicalize(&path).unwrap());
+        assets.poll_reload();
+        let render = assets.convert::<Upper>(handle.clone(), &()).unwrap();
+        assert_eq!(render.0, "V2");
+        assert_eq!(assets.render_generation::<Upper>(&handle), Some(2));
+    }
+
+    #[test]
+    fn get_mut_copies_shared_assets() {
+        let mut assets = Assets::new();
+        let original = assets.insert(Text("shared".to_string()));
+        let shared = assets.insert_shared(&original).unwrap();
+
+        assets.get_mut(shared.clone()).unwrap().0 = "changed".to_string();
+        assert_eq!(assets.get(original).unwrap().0, "shared");
+        assert_eq!(assets.get(shared).unwrap().0, "changed");
+    }
+
+    #[test]
+    fn get_mut_refuses_shared_assets_without_copy_on_write() {
+        let dir = temp_dir();
+        fs::write(dir.join("a.txt"), "same").unwrap();
+        fs::write(dir.join("b.txt"), "same").unwrap();
+        let mut assets = Assets::new();
+        let a = assets.load::<Text>(&dir.join("a.txt"), true);
+        let b = assets.load::<Text>(&dir.join("b.txt"), true);
+        assert_eq!(assets.dedup(), 1);
+
+        assert!(assets.get_mut(a.clone()).is_none());
+        assets.register_copy_on_write::<Text>();
+        assets.get_mut(a.clone()).unwrap().0 = "changed".to_string();
+        assert_eq!(assets.get(b).unwrap().0, "same");
+    }
+
+    #[test]
+    fn unload_scope_removes_exactly_its_assets() {
+        let dir = temp_dir();
+        fs::write(dir.join("level.txt"), "level").unwrap();
+        fs::write(dir.join("menu.txt"), "menu").unwrap();
+        let mut assets = Assets::new();
+        let level = assets.new_scope();
+        let menu = assets.new_scope();
+        let in_level = assets.load_in::<Text>(level, &dir.join("level.txt"), true);
+        let in_menu = assets.load_in::<Text>(menu, &dir.join("menu.txt"), true);
+        let inserted = assets.insert(Text("inserted".to_string()));
+        assets.add_to_scope(level, &inserted);
+        let unscoped = assets.insert(Text("unscoped".to_string()));
+
+        assets.unload_scope(level);
+        assert!(!assets.contains(in_level));
+        assert!(!assets.contains(inserted));
+        assert!(assets.contains(in_menu));
+        assert!(assets.contains(unscoped));
+    }
+
+    #[test]
+    fn unload_scope_unloads_child_scopes() {
+        let mut assets = Assets::new();
+        let parent = assets.new_scope();
+        let child = assets.new_child_scope(parent);
+        let in_parent = assets.insert(Text("parent".to_string()));
+        let in_child = assets.insert(Text("child".to_string()));
+        assets.add_to_scope(parent, &in_parent);
+        assets.add_to_scope(child, &in_child);
+
+        assets.unload_scope(parent);
+        assert!(!assets.contains(in_parent));
+        assert!(!assets.contains(in_child));
+    }
+
+    #[test]
+    fn loads_colliding_with_inserted_ids_replace_them_by_default() {
+        let dir = temp_dir();
+        fs::write(dir.join("loaded.txt"), "loaded").unwrap();
+        let mut assets = Assets::new();
+        let loading = assets.load::<Text>(&dir.join("loaded.txt"), false);
+        let inserted = assets.insert_with_id(loading.id, Text("inserted".to_string()));
+
+        assets.flush();
+        assert_eq!(assets.get(inserted).unwrap().0, "loaded");
+    }
+
+    #[test]
+    fn loads_colliding_with_inserted_ids_can_be_rejected() {
+        let dir = temp_dir();
+        fs::write(dir.join("loaded.txt"), "loaded").unwrap();
+        let mut assets = Assets::new();
+        assets.set_id_collision_policy(IdCollisionPolicy::Reject);
+        let loading = assets.load::<Text>(&dir.join("loaded.txt"), false);
+        let inserted = assets.insert_with_id(loading.id, Text("inserted".to_string()));
+
+        assets.flush();
+        assert_eq!(assets.get(inserted).unwrap().0, "inserted");
+    }
+
+    #[test]
+    fn explicit_ids_are_not_reused() {
+        let mut assets = Assets::new();
+        let explicit = assets.insert_with_id(1 << 40, Text("explicit".to_string()));
+        let next = assets.insert(Text("next".to_string()));
+        assert!(next.id > explicit.id);
+    }
+
+    #[test]
+    fn touching_a_file_makes_its_asset_stale() {
+        let dir = temp_dir();
+        let path = dir.join("data.txt");
+        fs::write(&path, "data").unwrap();
+        let mut assets = Assets::new();
+        let handle = assets.load::<Text>(&path, true);
+        assert!(!assets.is_stale(&handle));
+        assert_eq!(assets.file_mtime(&handle), modified_time(&path));
+
+        let touched = SystemTime::now() + Duration::from_secs(10);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(touched)
+            .unwrap();
+        assert!(assets.is_stale(&handle));
+        assert_eq!(assets.file_mtime(&handle), Some(touched));
+    }
+
+    #[test]
+    fn inserted_assets_are_never_stale() {
+        let mut assets = Assets::new();
+        let handle = assets.insert(Text("inserted".to_string()));
+        assert!(!assets.is_stale(&handle));
+        assert_eq!(assets.file_mtime(&handle), None);
+    }
+
+    #[test]
+    fn changed_reports_each_change_once() {
+        let dir = temp_dir();
+        let path = dir.join("data.txt");
+        fs::write(&path, "v1").unwrap();
+        let mut assets = Assets::new();
+        let handle = assets.load_watch_write::<Text>(&path, true);
+
+        let changes = assets.changed();
+        assert!(changes.loaded.contains(&handle.erase()));
+        assert!(assets.changed().is_empty());
+
+        fs::write(&path, "v2").unwrap();
+        assets.force_reload(fs::canonicalize(&path).unwrap());
+        assets.poll_reload();
+        assets.get_mut(handle.clone()).unwrap().0 = "v3".to_string();
+        assets.poll_write();
+
+        let changes = assets.changed();
+        assert_eq!(changes.loaded.len(), 0);
+        assert_eq!(changes.reloaded.len(), 1);
+        assert_eq!(changes.written.len(), 1);
+        assert!(assets.changed().is_empty());
+    }
+
+    #[test]
+    fn on_written_fires_once_with_the_write_result() {
+        let dir = temp_dir();
+        let path = dir.join("data.txt");
+        fs::write(&path, "v1").unwrap();
+        let mut assets = Assets::new();
+        let handle = assets.load_write::<Text>(&path, true);
+        let (sender, receiver) = mpsc::channel();
+        assets.on_written(&handle, move |result| sender.send(result.is_ok()).unwrap());
+
+        assets.poll_write();
+        assert!(receiver.try_recv().is_err());
+
+        assets.get_mut(handle.clone()).unwrap().0 = "v2".to_string();
+        assets.poll_write();
+        assert_eq!(receiver.try_recv(), Ok(true));
+
+        // later writes do not fire the callback again
+        assets.get_mut(handle.clone()).unwrap().0 = "v3".to_string();
+        assets.poll_write();
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn cancel_all_loads_keeps_results_out_of_the_cache() {
+        let dir = temp_dir();
+        let mut assets = Assets::with_workers(1);
+        assets.set_simulated_delay(Some(Duration::from_millis(20)));
+        let handles: Vec<AssetHandle<Text>> = (0..3)
+            .map(|i| {
+                let path = dir.join(format!("{}.txt", i));
+                fs::write(&path, "data").unwrap();
+                assets.load(&path, false)
+            })
+            .collect();
+
+        assets.cancel_all_loads();
+        assert!(
+            handles
+                .iter()
+                .all(|handle| !assets.is_pending(handle.clone()))
+        );
+
+        std::thread::sleep(Duration::from_millis(100));
+        assets.flush();
+        assert!(
+            handles
+                .iter()
+                .all(|handle| !assets.contains(handle.clone()))
+        );
+    }
+
+    #[test]
+    fn refresh_render_finds_the_render_asset_without_the_source() {
+        let mut assets = Assets::new();
+        let handle = assets.insert(Text("shader".to_string()));
+        let render = assets.convert::<Upper>(handle.clone(), &()).unwrap();
+        let refreshed = assets.refresh_render(&render).unwrap();
+        assert_eq!(refreshed.id(), render.id());
+
+        // changing the source drops the render asset until it is converted again
+        assets.get_mut(handle.clone()).unwrap().0 = "changed".to_string();
+        assert!(assets.refresh_render(&render).is_none());
+        let converted = assets.convert::<Upper>(handle, &()).unwrap();
+        assert!(assets.refresh_render(&render).is_none());
+        assert_eq!(assets.refresh_render(&converted).unwrap().0, "CHANGED");
+    }
+
+    #[test]
+    fn set_root_to_exe_dir_loads_next_to_the_executable() {
+        let exe_dir = std::env::current_exe()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .to_path_buf();
+        let name = format!("assets-test-fixture-{}.txt", std::process::id());
+        fs::write(exe_dir.join(&name), "bundled").unwrap();
+
+        let mut assets = Assets::new();
+        assets.set_root_to_exe_dir().unwrap();
+        let handle = assets.load::<Text>(Path::new(&name), true);
+        let loaded = assets.get(handle).map(|text| text.0.clone());
+        fs::remove_file(exe_dir.join(&name)).unwrap();
+        assert_eq!(loaded.as_deref(), Some("bundled"));
+    }
+
+    // asset aggregating every file of a directory
+    struct Folder(Vec<String>);
+
+    impl Asset for Folder {}
+
+    impl LoadableAsset for Folder {
+        fn load(path: &Path) -> Result<Self, AssetLoadError> {
+            let mut files = Vec::new();
+            for entry in fs::read_dir(path)? {
+                files.push(fs::read_to_string(entry?.path())?);
+            }
+            files.sort();
+            Ok(Folder(files))
+        }
+    }
+
+    #[test]
+    fn changing_a_file_reloads_the_directory_asset() {
+        let dir = temp_dir();
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        fs::write(dir.join("b.txt"), "b").unwrap();
+        let mut assets = Assets::new();
+        let handle = assets.load_dir_as::<Folder>(&dir, true);
+        assert_eq!(assets.get(handle.clone()).unwrap().0, ["a", "b"]);
+
+        fs::write(dir.join("b.txt"), "c").unwrap();
+        assets.force_reload(fs::canonicalize(dir.join("b.txt")).unwrap());
+        assets.poll_reload();
+        assert_eq!(assets.get(handle.clone()).unwrap().0, ["a", "c"]);
+        assert!(assets.changed().reloaded.contains(&handle.erase()));
+    }
+
+    // asset recording how many of its loads ran at the same time
+    struct Probe;
+
+    static PROBES_RUNNING: AtomicUsize = AtomicUsize::new(0);
+    static PROBES_MAX: AtomicUsize = AtomicUsize::new(0);
+
+    impl Asset for Probe {}
+
+    impl LoadableAsset for Probe {
+        fn load(_path: &Path) -> Result<Self, AssetLoadError> {
+            let running = PROBES_RUNNING.fetch_add(1, SeqCst) + 1;
+            PROBES_MAX.fetch_max(running, SeqCst);
+            std::thread::sleep(Duration::from_millis(30));
+            PROBES_RUNNING.fetch_sub(1, SeqCst);
+            Ok(Probe)
+        }
+    }
+
+    #[test]
+    fn load_concurrency_of_one_serializes_loads() {
+        let dir = temp_dir();
+        let mut assets = Assets::with_workers(4);
+        assets.set_load_concurrency(1);
+        let handles: Vec<AssetHandle<Probe>> = (0..2)
+            .map(|i| {
+                let path = dir.join(format!("{}.probe", i));
+                fs::write(&path, "").unwrap();
+                assets.load(&path, false)
+            })
+            .collect();
+
+        assets.flush();
+        assert!(handles.iter().all(|handle| assets.contains(handle.clone())));
+        assert_eq!(PROBES_MAX.load(SeqCst), 1);
+    }
+
+    // texture whose filter is set in its sidecar file
+    struct Texture {
+        data: String,
+        filter: String,
+    }
+
+    #[derive(Default)]
+    struct TextureSettings {
+        filter: String,
+    }
+
+    impl ImportSettings for TextureSettings {
+        fn parse(content: &str) -> Self {
+            TextureSettings {
+                filter: content.trim().to_string(),
+            }
+        }
+    }
+
+    impl Asset for Texture {}
+
+    impl ConfigurableLoadableAsset for Texture {
+        type ImportSettings = TextureSettings;
+
+        fn load(path: &Path, settings: &TextureSettings) -> Result<Self, AssetLoadError> {
+            Ok(Texture {
+                data: fs::read_to_string(path)?,
+                filter: settings.filter.clone(),
+            })
+        }
+    }
+
+    #[test]
+    fn editing_the_sidecar_reloads_with_new_settings() {
+        let dir = temp_dir();
+        let path = dir.join("grass.png");
+        fs::write(&path, "pixels").unwrap();
+        fs::write(dir.join("grass.import"), "nearest").unwrap();
+        let mut assets = Assets::new();
+        let handle = assets.load_configurable_watch::<Texture>(&path, true);
+        assert_eq!(assets.get(handle.clone()).unwrap().filter, "nearest");
+
+        fs::write(dir.join("grass.import"), "linear").unwrap();
+        assets.force_reload(fs::canonicalize(dir.join("grass.import")).unwrap());
+        assets.poll_reload();
+        let texture = assets.get(handle).unwrap();
+        assert_eq!(texture.filter, "linear");
+        assert_eq!(texture.data, "pixels");
+    }
+
+    #[test]
+    fn missing_sidecars_use_default_settings() {
+        let dir = temp_dir();
+        fs::write(dir.join("grass.png"), "pixels").unwrap();
+        let mut assets = Assets::new();
+        let handle = assets.load_configurable::<Texture>(&dir.join("grass.png"), true);
+        assert_eq!(assets.get(handle).unwrap().filter, "");
+    }
+
+    #[test]
+    fn iter_render_lists_every_converted_source() {
+        let mut assets = Assets::new();
+        let a = assets.insert(Text("a".to_string()));
+        let b = assets.insert(Text("b".to_string()));
+        assets.insert(Text("not converted".to_string()));
+        assets.convert::<Upper>(a.clone(), &()).unwrap();
+        assets.convert::<Upper>(b.clone(), &()).unwrap();
+        assets.convert::<Length>(a.clone(), &()).unwrap();
+
+        let mut renders: Vec<(u64, String)> = assets
+            .iter_render::<Upper>()
+            .map(|(source, render)| (source.id, render.0.clone()))
+            .collect();
+        renders.sort();
+        assert_eq!(renders, [(a.id, "A".to_string()), (b.id, "B".to_string())]);
+        let lengths: Vec<usize> = assets
+            .iter_render::<Length>()
+            .map(|(_, render)| render.0)
+            .collect();
+        assert_eq!(lengths, [1]);
+    }
+
+    #[test]
+    fn flush_applies_loads_scheduled_just_before() {
+        let dir = temp_dir();
+        fs::write(dir.join("data.txt"), "data").unwrap();
+        let mut assets = Assets::new();
+        assets.set_simulated_delay(Some(Duration::from_millis(20)));
+        let handle = assets.load::<Text>(&dir.join("data.txt"), false);
+        assert!(assets.is_pending(handle.clone()));
+
+        assets.flush();
+        assert!(!assets.is_pending(handle.clone()));
+        assert_eq!(assets.get(handle).unwrap().0, "data");
+    }
+
+    #[test]
+    fn flush_waits_for_async_conversions() {
+        let mut assets = Assets::new();
+        let handle = assets.insert(Text("shader".to_string()));
+        assets.convert_async::<Upper>(handle.clone(), &());
+
+        assets.flush();
+        assert!(assets.render_ready(&[handle.erase()]));
+    }
+
+    #[test]
+    fn bump_recipe_converts_again() {
+        let mut assets = Assets::new();
+        let handle = assets.insert(Text("shader".to_string()));
+        assets.convert::<Upper>(handle.clone(), &()).unwrap();
+        assets.convert::<Length>(handle.clone(), &()).unwrap();
+
+        assets.bump_recipe::<Upper>();
+        assets.convert::<Upper>(handle.clone(), &()).unwrap();
+        assets.convert::<Length>(handle.clone(), &()).unwrap();
+        assert_eq!(assets.convert_count::<Upper>(&handle), Some(2));
+        assert_eq!(assets.convert_count::<Length>(&handle), Some(1));
+    }
+
+    // one integer per line
+    struct Numbers(Vec<i64>);
+
+    impl Asset for Numbers {}
+
+    impl LoadableAsset for Numbers {
+        fn load(path: &Path) -> Result<Self, AssetLoadError> {
+            let content = fs::read_to_string(path)?;
+            let mut numbers = Vec::new();
+            for (line, text) in content.lines().enumerate() {
+                let number = text.trim().parse().map_err(|_| AssetLoadError::ParseAt {
+                    line: line as u32 + 1,
+                    column: (text.len() - text.trim_start().len()) as u32 + 1,
+                    message: "invalid integer".to_string(),
+                })?;
+                numbers.push(number);
+            }
+            Ok(Numbers(numbers))
+        }
+    }
+
+    #[test]
+    fn malformed_files_report_where_they_failed() {
+        let dir = temp_dir();
+        let path = dir.join("numbers.txt");
+        fs::write(&path, "1\n    two\n3").unwrap();
+        let mut assets = Assets::new();
+        let handle = assets.load::<Numbers>(&path, true);
+
+        let err = assets.load_error(&handle).unwrap();
+        let canonical = fs::canonicalize(&path).unwrap();
+        assert_eq!(err.path(), Some(canonical.as_path()));
+        assert_eq!(err.location(), Some((2, 5)));
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "failed to load {}:2:5: invalid integer",
+                canonical.display()
+            )
+        );
+    }
+
+    #[test]
+    fn async_load_errors_carry_the_path() {
+        let dir = temp_dir();
+        let path = dir.join("numbers.txt");
+        fs::write(&path, "x").unwrap();
+        let mut assets = Assets::new();
+        let handle = assets.load::<Numbers>(&path, false);
+
+        assets.flush();
+        let err = assets.load_error(&handle).unwrap();
+        assert_eq!(err.path(), Some(fs::canonicalize(&path).unwrap().as_path()));
+        assert_eq!(err.location(), Some((1, 1)));
+    }
+
+    #[test]
+    fn well_formed_files_have_no_load_error() {
+        let dir = temp_dir();
+        fs::write(dir.join("numbers.txt"), "1\n2").unwrap();
+        let mut assets = Assets::new();
+        let handle = assets.load::<Numbers>(&dir.join("numbers.txt"), true);
+        assert!(assets.load_error(&handle).is_none());
+        assert_eq!(assets.get(handle).unwrap().0, [1, 2]);
+    }
+
+    #[test]
+    fn load_default_uses_the_options_of_the_type() {
+        let dir = temp_dir();
+        fs::write(dir.join("data.txt"), "data").unwrap();
+        let mut assets = Assets::new();
+        let options = LoadOptions {
+            sync: true,
+            watch: true,
+            write: false,
+        };
+        assets.set_default_options::<Text>(options);
+        assert_eq!(assets.default_options::<Text>(), options);
+
+        let handle = assets.load_default::<Text>(&dir.join("data.txt"));
+        assert_eq!(assets.get(handle.clone()).unwrap().0, "data");
+        assert_eq!(
+            assets.handles_for_path(&dir.join("data.txt")),
+            [handle.erase()]
+        );
+        assert_eq!(assets.stats().watched_paths, 1);
+    }
+
+    #[test]
+    fn types_without_options_use_the_global_default() {
+        let mut assets = Assets::new();
+        let global = LoadOptions {
+            sync: true,
+            watch: false,
+            write: true,
+        };
+        assets.set_global_default_options(global);
+        assets.set_default_options::<Text>(LoadOptions::default());
+        assert_eq!(assets.default_options::<Numbers>(), global);
+        assert_eq!(assets.default_options::<Text>(), LoadOptions::default());
+    }
+
+    // source serving files from memory, recording every ranged read
+    #[derive(Default)]
+    struct MemorySource {
+        files: HashMap<PathBuf, Vec<u8>>,
+        ranges: Arc<Mutex<Vec<std::ops::Range<u64>>>>,
+        ranged: bool,
+    }
+
+    impl AssetSource for MemorySource {
+        fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+            self.files
+                .get(path)
+                .cloned()
+                .ok_or_else(|| std::io::ErrorKind::NotFound.into())
+        }
+
+        fn as_range_readable(&self) -> Option<&dyn RangeReadableSource> {
+            self.ranged.then_some(self as &dyn RangeReadableSource)
+        }
+    }
+
+    impl RangeReadableSource for MemorySource {
+        fn read_range(&self, path: &Path, range: std::ops::Range<u64>) -> std::io::Result<Vec<u8>> {
+            self.ranges.lock().unwrap().push(range.clone());
+            let bytes = self.read(path)?;
+            Ok(bytes[range.start as usize..range.end as usize].to_vec())
+        }
+    }
+
+    // name stored in the first four bytes of a pack
+    struct PackName(String);
+
+    impl Asset for PackName {}
+
+    impl RangedLoadableAsset for PackName {
+        fn load_ranged(
+            path: &Path,
+            source: &dyn RangeReadableSource,
+        ) -> Result<Self, AssetLoadError> {
+            let bytes = source.read_range(path, 0..4)?;
+            Ok(PackName(String::from_utf8_lossy(&bytes).into_owned()))
+        }
+    }
+
+    #[test]
+    fn load_ranged_reads_only_the_requested_ranges() {
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let mut files = HashMap::new();
+        files.insert(PathBuf::from("level.pack"), b"mesh and a lot more".to_vec());
+        let mut assets = Assets::new();
+        assets.set_source(Box::new(MemorySource {
+            files,
+            ranges: ranges.clone(),
+            ranged: true,
+        }));
+
+        let sync = assets.load_ranged::<PackName>(Path::new("level.pack"), true);
+        assert_eq!(assets.get(sync).unwrap().0, "mesh");
+        let ranges = ranges.lock().unwrap();
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(ranges[0], 0..4);
+    }
+
+    #[test]
+    fn load_ranged_runs_on_workers() {
+        let dir = temp_dir();
+        fs::write(dir.join("level.pack"), "mesh and a lot more").unwrap();
+        let mut assets = Assets::new();
+        let handle = assets.load_ranged::<PackName>(&dir.join("level.pack"), false);
+
+        assets.flush();
+        assert_eq!(assets.get(handle).unwrap().0, "mesh");
+    }
+
+    #[test]
+    fn load_ranged_fails_for_sources_without_ranges() {
+        let mut files = HashMap::new();
+        files.insert(PathBuf::from("level.pack"), b"mesh".to_vec());
+        let mut assets = Assets::new();
+        assets.set_source(Box::new(MemorySource {
+            files,
+            ..Default::default()
+        }));
+
+        let handle = assets.load_ranged::<PackName>(Path::new("level.pack"), true);
+        let err = assets.load_error(&handle).unwrap();
+        assert!(
+            matches!(err, AssetLoadError::File { source, .. } if matches!(**source, AssetLoadError::Io(ref io) if io.kind() == std::io::ErrorKind::Unsupported))
+        );
+    }
+
+    #[test]
+    fn canonical_path_returns_the_normalized_path() {
+        let dir = temp_dir();
+        fs::create_dir(dir.join("sub")).unwrap();
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        let input = dir.join("sub").join("..").join("a.txt");
+        let mut assets = Assets::new();
+        let handle = assets.load_with_options::<Text>(
+            &input,
+            LoadOptions {
+                sync: true,
+                watch: true,
+                write: false,
+            },
+        );
+
+        let canonical = assets.canonical_path(&handle).unwrap();
+        assert_eq!(canonical, fs::canonicalize(&input).unwrap());
+        assert_ne!(canonical, input);
+    }
+
+    #[test]
+    fn arc_keeps_the_old_value_after_get_mut() {
+        let dir = temp_dir();
+        fs::write(dir.join("a.txt"), "old").unwrap();
+        let mut assets = Assets::new();
+        assets.register_copy_on_write::<Text>();
+        let handle = assets.load::<Text>(&dir.join("a.txt"), true);
+
+        let arc = assets.get_arc(&handle).unwrap();
+        assets.get_mut(handle.clone()).unwrap().0 = "new".to_string();
+        assert_eq!(arc.0, "old");
+        assert_eq!(assets.get(handle).unwrap().0, "new");
+    }
+
+    #[test]
+    fn shared_assets_without_copy_on_write_are_not_mutable() {
+        let dir = temp_dir();
+        fs::write(dir.join("a.txt"), "old").unwrap();
+        let mut assets = Assets::new();
+        let handle = assets.load::<Text>(&dir.join("a.txt"), true);
+
+        let arc = assets.get_arc(&handle).unwrap();
+        assert!(assets.get_mut(handle.clone()).is_none());
+        drop(arc);
+        assert!(assets.get_mut(handle).is_some());
+    }
+
+    #[test]
+    fn watcher_stats_count_raw_events_and_coalesced_reloads() {
+        let dir = temp_dir();
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        let mut assets = Assets::with_debounce(Duration::from_millis(20));
+        let handle = assets.load_dir_as::<Folder>(&dir, true);
+
+        for name in ["b.txt", "c.txt", "d.txt"] {
+            fs::write(dir.join(name), name).unwrap();
+        }
+        let start = Instant::now();
+        while assets.watcher_stats().raw_events < 3 {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "no watcher events"
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assets.poll_reload();
+
+        let stats = assets.watcher_stats();
+        assert_eq!(stats.coalesced_reloads, 1);
+        assert!(stats.raw_events > stats.coalesced_reloads);
+        assert_eq!(assets.get(handle).unwrap().0.len(), 4);
+    }
+
+    #[test]
+    fn shared_render_cache_shares_conversions_between_instances() {
+        let dir = temp_dir();
+        fs::write(dir.join("a.txt"), "shared").unwrap();
+        let cache = Arc::new(Mutex::new(SharedRenderCache::new()));
+        let mut first = Assets::new();
+        let mut second = Assets::new();
+        first.set_shared_render_cache(cache.clone());
+        second.set_shared_render_cache(cache);
+        let first_handle = first.load::<Text>(&dir.join("a.txt"), true);
+        let second_handle = second.load::<Text>(&dir.join("a.txt"), true);
+
+        let converted = first.convert::<Upper>(first_handle, &()).unwrap();
+        let shared = second.convert::<Upper>(second_handle, &()).unwrap();
+        assert_eq!(shared.0, "SHARED");
+        assert!(converted == shared);
+    }
+
+    #[test]
+    fn shared_render_cache_skips_modified_sources() {
+        let dir = temp_dir();
+        fs::write(dir.join("a.txt"), "shared").unwrap();
+        let cache = Arc::new(Mutex::new(SharedRenderCache::new()));
+        let mut first = Assets::new();
+        let mut second = Assets::new();
+        first.set_shared_render_cache(cache.clone());
+        second.set_shared_render_cache(cache);
+        let first_handle = first.load::<Text>(&dir.join("a.txt"), true);
+        let second_handle = second.load::<Text>(&dir.join("a.txt"), true);
+
+        first.convert::<Upper>(first_handle, &()).unwrap();
+        second.get_mut(second_handle.clone()).unwrap().0 = "own".to_string();
+        assert_eq!(
+            second.convert::<Upper>(second_handle, &()).unwrap().0,
+            "OWN"
+        );
+    }
+
+    #[test]
+    fn frozen_assets_are_not_mutable_or_dirty() {
+        let dir = temp_dir();
+        fs::write(dir.join("a.txt"), "config").unwrap();
+        let mut assets = Assets::new();
+        let handle = assets.load::<Text>(&dir.join("a.txt"), true);
+
+        assets.freeze(&handle);
+        assert!(assets.is_frozen(&handle));
+        assert!(assets.get_mut(handle.clone()).is_none());
+        assert!(assets.load_dirty.is_empty());
+
+        assets.unfreeze(&handle);
+        assert!(assets.get_mut(handle.clone()).is_some());
+        assert!(assets.load_dirty.contains(&handle.erase()));
+    }
+
+    #[test]
+    fn freezing_drops_scheduled_writes() {
+        let dir = temp_dir();
+        fs::write(dir.join("a.txt"), "config").unwrap();
+        let mut assets = Assets::new();
+        let handle = assets.load::<Text>(&dir.join("a.txt"), true);
+
+        assets.get_mut(handle.clone()).unwrap().0 = "changed".to_string();
+        assets.freeze(&handle);
+        assert!(assets.load_dirty.is_empty());
+    }
+
+    #[test]
+    fn warm_render_makes_every_source_ready() {
+        let dir = temp_dir();
+        let mut assets = Assets::new();
+        let handles: Vec<AssetHandle<Text>> = ["a", "b", "c"]
+            .into_iter()
+            .map(|name| {
+                let path = dir.join(name);
+                fs::write(&path, name).unwrap();
+                assets.load::<Text>(&path, true)
+            })
+            .collect();
+        let erased: Vec<AssetHandle<DynAsset>> = handles.iter().map(|h| h.erase()).collect();
+
+        assets.convert::<Upper>(handles[0].clone(), &());
+        assert!(!assets.render_ready(&erased));
+
+        assets.warm_render::<Upper>(&handles, &());
+        assert!(assets.render_ready(&erased));
+        assert_eq!(assets.render_cache.len(), 3);
+    }
+
+    #[test]
+    fn tick_applies_reloads_before_converting() {
+        let dir = temp_dir();
+        let path = dir.join("shader.txt");
+        fs::write(&path, "old").unwrap();
+        let mut assets = Assets::new();
+        let handle = assets.load_with_options::<Text>(
+            &path,
+            LoadOptions {
+                sync: true,
+                watch: true,
+                write: false,
+            },
+        );
+        assert_eq!(
+            assets.convert::<Upper>(handle.clone(), &()).unwrap().0,
+            "OLD"
+        );
+
+        fs::write(&path, "new").unwrap();
+        assets.force_reload(fs::canonicalize(&path).unwrap());
+        assets.tick();
+        assets.flush();
+        assert_eq!(assets.convert::<Upper>(handle, &()).unwrap().0, "NEW");
+    }
+
+    #[test]
+    fn render_assets_of_outdated_sources_are_converted_again() {
+        let dir = temp_dir();
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        let mut assets = Assets::new();
+        let handle = assets.load::<Text>(&dir.join("a.txt"), true);
+        assets.convert::<Upper>(handle.clone(), &());
+
+        // source changed without its render asset being evicted
+        *assets.source_versions.entry(handle.erase()).or_insert(0) += 1;
+        assets.convert::<Upper>(handle.clone(), &());
+        let counter = (handle.erase(), TypeId::of::<Upper>());
+        assert_eq!(assets.convert_misses[&counter], 2);
+    }
+
+    impl SnapshotableAsset for Text {
+        fn to_snapshot(&self) -> Vec<u8> {
+            self.0.as_bytes().to_vec()
+        }
+
+        fn from_snapshot(bytes: &[u8]) -> Option<Self> {
+            String::from_utf8(bytes.to_vec()).ok().map(Text)
+        }
+    }
+
+    #[test]
+    fn snapshot_round_trip_keeps_ids_and_values() {
+        let dir = temp_dir();
+        fs::write(dir.join("a.txt"), "from file").unwrap();
+        let mut saved = Assets::new();
+        saved.register_snapshot_type::<Text>("text");
+        let loaded = saved.load::<Text>(&dir.join("a.txt"), true);
+        let inserted = saved.insert(Text("inserted".to_string()));
+        assert_eq!(saved.save_snapshot(&dir.join("cache.snap")).unwrap(), 2);
+
+        let mut restored = Assets::new();
+        restored.register_snapshot_type::<Text>("text");
+        let handles = restored.load_snapshot(&dir.join("cache.snap")).unwrap();
+        assert_eq!(handles.len(), 2);
+        for (original, value) in [
+            (loaded.erase(), "from file"),
+            (inserted.erase(), "inserted"),
+        ] {
+            let handle = handles.iter().find(|h| h.id == original.id).unwrap();
+            let handle = handle.downcast::<Text>().unwrap();
+            assert_eq!(restored.get(handle).unwrap().0, value);
+        }
+    }
+
+    #[test]
+    fn snapshot_reloads_files_changed_since_saving() {
+        let dir = temp_dir();
+        let path = dir.join("a.txt");
+        fs::write(&path, "old").unwrap();
+        let mut saved = Assets::new();
+        saved.register_snapshot_type::<Text>("text");
+        saved.load::<Text>(&path, true);
+        saved.save_snapshot(&dir.join("cache.snap")).unwrap();
+
+        fs::write(&path, "new").unwrap();
+        let later = SystemTime::now() + Duration::from_secs(10);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+
+        let mut restored = Assets::new();
+        restored.register_snapshot_type::<Text>("text");
+        let handles = restored.load_snapshot(&dir.join("cache.snap")).unwrap();
+        let handle = handles[0].downcast::<Text>().unwrap();
+        assert_eq!(restored.get(handle).unwrap().0, "new");
+    }
+
+    #[test]
+    fn snapshot_with_unknown_types_fails() {
+        let dir = temp_dir();
+        let mut saved = Assets::new();
+        saved.register_snapshot_type::<Text>("text");
+        saved.insert(Text("inserted".to_string()));
+        saved.save_snapshot(&dir.join("cache.snap")).unwrap();
+
+        let mut restored = Assets::new();
+        let err = restored.load_snapshot(&dir.join("cache.snap")).unwrap_err();
+        assert!(matches!(err, AssetError::UnknownType(name) if name == "text"));
+    }
+
+    #[test]
+    fn identical_content_shares_one_render_asset() {
+        let dir = temp_dir();
+        fs::write(dir.join("a.txt"), "same").unwrap();
+        fs::write(dir.join("b.txt"), "same").unwrap();
+        fs::write(dir.join("c.txt"), "other").unwrap();
+        let mut assets = Assets::new();
+        assets.set_render_content_dedup(true);
+        let a = assets.load::<Text>(&dir.join("a.txt"), true);
+        let b = assets.load::<Text>(&dir.join("b.txt"), true);
+        let c = assets.load::<Text>(&dir.join("c.txt"), true);
+
+        let first = assets.convert::<Upper>(a, &()).unwrap();
+        let second = assets.convert::<Upper>(b.clone(), &()).unwrap();
+        let other = assets.convert::<Upper>(c, &()).unwrap();
+        assert!(Arc::ptr_eq(&first.handle, &second.handle));
+        assert!(!Arc::ptr_eq(&first.handle, &other.handle));
+        let counter = (b.erase(), TypeId::of::<Upper>());
+        assert!(!assets.convert_misses.contains_key(&counter));
+    }
+
+    #[test]
+    fn modified_sources_do_not_share_render_assets() {
+        let dir = temp_dir();
+        fs::write(dir.join("a.txt"), "same").unwrap();
+        fs::write(dir.join("b.txt"), "same").unwrap();
+        let mut assets = Assets::new();
+        assets.set_render_content_dedup(true);
+        let a = assets.load::<Text>(&dir.join("a.txt"), true);
+        let b = assets.load::<Text>(&dir.join("b.txt"), true);
+
+        assets.convert::<Upper>(a, &()).unwrap();
+        assets.get_mut(b.clone()).unwrap().0 = "edited".to_string();
+        assert_eq!(assets.convert::<Upper>(b, &()).unwrap().0, "EDITED");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_paths_load_and_debug() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let dir = temp_dir();
+        let path = dir.join(OsStr::from_bytes(b"bad\xff.txt"));
+        fs::write(&path, "bytes").unwrap();
+        let mut assets = Assets::new();
+        assets.register_snapshot_type::<Text>("text");
+        assets.register_type::<Text>("text");
+        let handle = assets.load_with_options::<Text>(
+            &path,
+            LoadOptions {
+                sync: true,
+                watch: true,
+                write: false,
+            },
+        );
+        assert_eq!(assets.get(handle.clone()).unwrap().0, "bytes");
+        assert_eq!(assets.canonical_path(&handle), Some(path.as_path()));
+        assert!(format!("{:?}", assets.canonical_path(&handle)).contains("bad"));
+        assert!(assets.persistent_id(&handle).is_some());
 
-                    // invalidate render cache
-                    self.render_cache.remove(handle);
-                }
+        assets.save_snapshot(&dir.join("cache.snap")).unwrap();
+        let mut restored = Assets::new();
+        restored.register_snapshot_type::<Text>("text");
+        let handles = restored.load_snapshot(&dir.join("cache.snap")).unwrap();
+        assert_eq!(handles[0].path(), Some(path.as_path()));
+    }
+
+    #[test]
+    fn repeated_converts_only_run_once() {
+        let dir = temp_dir();
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        let mut assets = Assets::new();
+        let handle = assets.load::<Text>(&dir.join("a.txt"), true);
+        assert_eq!(assets.convert_count::<Upper>(&handle), None);
+
+        for _ in 0..3 {
+            assets.convert::<Upper>(handle.clone(), &());
+        }
+        assert_eq!(assets.convert_count::<Upper>(&handle), Some(1));
+        assert_eq!(assets.convert_hit_count::<Upper>(&handle), Some(2));
+        assert_eq!(assets.convert_count::<Length>(&handle), None);
+
+        assets.get_mut(handle.clone()).unwrap().0 = "b".to_string();
+        assets.convert::<Upper>(handle.clone(), &());
+        assert_eq!(assets.convert_count::<Upper>(&handle), Some(2));
+    }
+
+    #[test]
+    fn on_removed_runs_once_with_the_removed_value() {
+        let dir = temp_dir();
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        let removed = Arc::new(Mutex::new(Vec::new()));
+        let mut assets = Assets::new();
+        let removed_copy = removed.clone();
+        assets.on_removed::<Text>(move |text| removed_copy.lock().unwrap().push(text.0.clone()));
+        let handle = assets.load::<Text>(&dir.join("a.txt"), true);
+
+        assert_eq!(assets.remove(handle.clone()), Some(Text("a".to_string())));
+        assert!(assets.remove(handle).is_none());
+        assert_eq!(*removed.lock().unwrap(), ["a"]);
+    }
+
+    #[test]
+    fn on_removed_runs_for_cleared_assets_in_order() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let mut assets = Assets::new();
+        for name in ["first", "second"] {
+            let calls = calls.clone();
+            assets
+                .on_removed::<Text>(move |text| calls.lock().unwrap().push((name, text.0.clone())));
+        }
+        assets.insert(Text("a".to_string()));
+
+        assets.clear();
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [("first", "a".to_string()), ("second", "a".to_string())]
+        );
+    }
+
+    // log with one entry per line
+    struct Log(Vec<String>);
+
+    impl Asset for Log {}
+
+    impl LoadableAsset for Log {
+        fn load(path: &Path) -> Result<Self, AssetLoadError> {
+            let text = fs::read_to_string(path)?;
+            Ok(Log(text.lines().map(str::to_string).collect()))
+        }
+    }
+
+    impl AppendableAsset for Log {
+        fn entries(&self) -> usize {
+            self.0.len()
+        }
+
+        fn append(&self, from: usize, file: &mut fs::File) {
+            use std::io::Write;
+            for line in &self.0[from..] {
+                writeln!(file, "{line}").expect("could not append line");
             }
         }
     }
 
-    pub fn force_reload(&self, path: PathBuf) {
-        self.reload_sender.send(path).expect("could not send path");
+    #[test]
+    fn appends_only_new_entries_across_writes() {
+        let dir = temp_dir();
+        let path = dir.join("log.txt");
+        fs::write(&path, "loaded\n").unwrap();
+        let mut assets = Assets::new();
+        let handle = assets.load_append::<Log>(&path, true);
+
+        assets
+            .get_mut(handle.clone())
+            .unwrap()
+            .0
+            .push("first".to_string());
+        assets.poll_write();
+        assets
+            .get_mut(handle.clone())
+            .unwrap()
+            .0
+            .push("second".to_string());
+        assets.poll_write();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "loaded\nfirst\nsecond\n"
+        );
+        assert!(assets.changed().written.contains(&handle.erase()));
     }
-}
 
-impl dyn Asset {
-    fn as_any(&self) -> &dyn Any {
-        self
+    #[test]
+    fn names_and_aliases_resolve_both_ways() {
+        let mut assets = Assets::new();
+        let handle = assets.insert_named("player", Text("a".to_string()));
+        assets.add_alias(&handle, "hero");
+        assets.add_alias(&handle, "main");
+        assert_eq!(assets.name_of(&handle), Some("player"));
+        assert_eq!(assets.aliases_of(&handle), ["hero", "main"]);
+        assert_eq!(assets.named::<Text>("hero"), Some(handle.clone()));
+
+        // the new owner takes the name and alias away from the old one
+        let other = assets.insert(Text("b".to_string()));
+        assets.set_name(&other, "player");
+        assets.add_alias(&other, "hero");
+        assert_eq!(assets.name_of(&handle), None);
+        assert_eq!(assets.aliases_of(&handle), ["main"]);
+        assert_eq!(assets.name_of(&other), Some("player"));
+
+        assets.remove(other.clone());
+        assert_eq!(assets.named::<Text>("player"), None);
+        assert_eq!(assets.named::<Text>("hero"), None);
     }
-    fn as_any_mut(&mut self) -> &mut dyn Any {
-        self
+
+    // asset recording the order files are written in
+    struct Recorded;
+
+    static RECORDED_WRITES: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+    impl Asset for Recorded {}
+
+    impl WriteableAsset for Recorded {
+        fn write(&mut self, path: &Path) {
+            RECORDED_WRITES.lock().unwrap().push(path.to_path_buf());
+        }
     }
-}
-impl dyn RenderAsset {
-    fn as_any(&self) -> &dyn Any {
-        self
+
+    #[test]
+    fn batched_save_all_writes_each_directory_in_one_pass() {
+        let dir = temp_dir();
+        let dirs = [dir.join("meshes"), dir.join("textures")];
+        for dir in &dirs {
+            fs::create_dir(dir).unwrap();
+        }
+        let mut assets = Assets::new();
+        assets.set_write_batch(true);
+        for i in 0..100 {
+            let handle = assets.insert(Recorded);
+            assets.write(handle, &dirs[i % 2].join(i.to_string()));
+        }
+
+        assets.save_all();
+        let writes = RECORDED_WRITES.lock().unwrap();
+        assert_eq!(writes.len(), 100);
+        // all files of one directory are written before moving on to the next
+        let switches = writes
+            .windows(2)
+            .filter(|pair| pair[0].parent() != pair[1].parent())
+            .count();
+        assert_eq!(switches, 1);
     }
-    fn as_any_mut(&mut self) -> &mut dyn Any {
-        self
+
+    #[test]
+    fn metadata_is_cleared_with_the_asset() {
+        let mut assets = Assets::new();
+        let handle = assets.insert(Text("a".to_string()));
+        assets.set_meta(&handle, "author", "ada".to_string());
+        assets.set_meta(&handle, "tag", "ui".to_string());
+        assets.set_meta(&handle, "tag", "hud".to_string());
+        assert_eq!(assets.get_meta(&handle, "author"), Some("ada"));
+        assert_eq!(assets.get_meta(&handle, "tag"), Some("hud"));
+        assert_eq!(
+            assets.remove_meta(&handle, "author"),
+            Some("ada".to_string())
+        );
+        assert_eq!(assets.get_meta(&handle, "author"), None);
+
+        assets.remove(handle.clone());
+        assert_eq!(assets.get_meta(&handle, "tag"), None);
     }
-}
 
-static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+    // float params quantized to tenths for the render cache key
+    struct Scale(f32);
 
-#[derive(Debug)]
-pub struct ArcHandle<T: ?Sized + 'static> {
-    pub handle: Arc<T>,
-    id: u64,
-}
+    impl ParamsKey for Scale {
+        fn params_key(&self) -> u64 {
+            (self.0 * 10.0).round() as u64
+        }
+    }
 
-impl<T: 'static> ArcHandle<T> {
-    pub fn new(handle: T) -> Self {
-        ArcHandle {
-            handle: Arc::new(handle),
-            id: NEXT_ID.fetch_add(1, SeqCst),
+    struct Scaled(f32);
+
+    impl RenderAsset for Scaled {}
+
+    impl ConvertableRenderAsset for Scaled {
+        type SourceAsset = Text;
+        type Params = Scale;
+
+        fn convert(source: &Text, params: &Scale) -> Result<Self, ConvertError> {
+            Ok(Scaled(source.0.len() as f32 * params.0))
         }
     }
 
-    #[inline]
-    pub fn id(&self) -> u64 {
-        self.id
+    #[test]
+    fn params_with_the_same_key_share_a_render_asset() {
+        let mut assets = Assets::new();
+        let handle = assets.insert(Text("ab".to_string()));
+
+        let first = assets
+            .convert::<Scaled>(handle.clone(), &Scale(1.0))
+            .unwrap();
+        let close = assets
+            .convert::<Scaled>(handle.clone(), &Scale(1.01))
+            .unwrap();
+        let other = assets
+            .convert::<Scaled>(handle.clone(), &Scale(2.0))
+            .unwrap();
+        assert!(first == close);
+        assert!(first != other);
+        assert_eq!(other.0, 4.0);
+        assert_eq!(assets.convert_count::<Scaled>(&handle), Some(2));
     }
-}
 
-impl<T: 'static> Clone for ArcHandle<T> {
-    fn clone(&self) -> Self {
-        ArcHandle {
-            handle: Arc::clone(&self.handle),
-            id: self.id,
+    #[test]
+    fn load_batch_until_returns_early_at_the_deadline() {
+        let dir = temp_dir();
+        let mut assets = Assets::new();
+        assets.set_simulated_delay(Some(Duration::from_millis(200)));
+        let handles: Vec<AssetHandle<DynAsset>> = (0..3)
+            .map(|i| {
+                let path = dir.join(i.to_string());
+                fs::write(&path, "a").unwrap();
+                assets.load::<Text>(&path, false).erase()
+            })
+            .collect();
+
+        let start = Instant::now();
+        let remaining = assets.load_batch_until(&handles, start + Duration::from_millis(20));
+        assert_eq!(remaining, 3);
+        assert!(start.elapsed() < Duration::from_millis(200));
+
+        let remaining = assets.load_batch_until(&handles, Instant::now() + Duration::from_secs(5));
+        assert_eq!(remaining, 0);
+    }
+
+    #[test]
+    fn inflight_bytes_stay_within_the_limit() {
+        let dir = temp_dir();
+        let mut assets = Assets::with_workers(4);
+        assets.set_inflight_byte_limit(Some(100));
+        let handles: Vec<AssetHandle<Text>> = (0..4)
+            .map(|i| {
+                let path = dir.join(i.to_string());
+                fs::write(&path, [b'a'; 60]).unwrap();
+                assets.load::<Text>(&path, false)
+            })
+            .collect();
+
+        // only one load fits until poll_loaded delivers it
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(assets.inflight_bytes(), 60);
+
+        let start = Instant::now();
+        while handles
+            .iter()
+            .any(|handle| assets.get(handle.clone()).is_none())
+        {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "loads never finished"
+            );
+            assert!(assets.inflight_bytes() <= 100);
+            assets.poll_loaded();
         }
+        assert_eq!(assets.inflight_bytes(), 0);
     }
-}
 
-impl<T: 'static> PartialEq for ArcHandle<T> {
-    fn eq(&self, other: &Self) -> bool {
-        self.id == other.id
+    #[test]
+    fn reload_interceptor_applies_skips_and_defers() {
+        use std::sync::atomic::AtomicBool;
+
+        let dir = temp_dir();
+        let mut assets = Assets::new();
+        let handles: Vec<AssetHandle<Text>> = ["apply", "skip", "defer"]
+            .into_iter()
+            .map(|name| {
+                fs::write(dir.join(name), "old").unwrap();
+                assets.load_with_options(
+                    &dir.join(name),
+                    LoadOptions {
+                        sync: true,
+                        watch: true,
+                        write: false,
+                    },
+                )
+            })
+            .collect();
+
+        let release = Arc::new(AtomicBool::new(false));
+        let previous = Arc::new(Mutex::new(Vec::new()));
+        let (release_copy, previous_copy) = (release.clone(), previous.clone());
+        assets.set_reload_interceptor(move |context| {
+            let text = context.previous(&context.handles[0].downcast::<Text>().unwrap());
+            previous_copy.lock().unwrap().push(text.unwrap().0.clone());
+            match context.path.file_name().unwrap().to_str().unwrap() {
+                "skip" => ReloadDecision::Skip,
+                "defer" if !release_copy.load(SeqCst) => ReloadDecision::Defer,
+                _ => ReloadDecision::Apply,
+            }
+        });
+
+        for name in ["apply", "skip", "defer"] {
+            fs::write(dir.join(name), "new").unwrap();
+            assets.force_reload(fs::canonicalize(dir.join(name)).unwrap());
+        }
+        assets.poll_reload();
+        let values = |assets: &Assets| -> Vec<String> {
+            handles
+                .iter()
+                .map(|handle| assets.get(handle.clone()).unwrap().0.clone())
+                .collect()
+        };
+        assert_eq!(values(&assets), ["new", "old", "old"]);
+        assert_eq!(*previous.lock().unwrap(), ["old", "old", "old"]);
+
+        // the deferred reload is asked about again on the next poll
+        release.store(true, SeqCst);
+        assets.poll_reload();
+        assert_eq!(values(&assets), ["new", "old", "new"]);
     }
-}
 
-impl<T: 'static> Eq for ArcHandle<T> {}
+    // second stage built from the upper case text
+    struct Shout(String);
 
-impl<T: 'static> std::hash::Hash for ArcHandle<T> {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.id.hash(state);
+    impl RenderAsset for Shout {}
+
+    impl ConvertStage for Shout {
+        type Previous = Upper;
+        type Params = usize;
+
+        fn convert(previous: &Upper, params: &usize) -> Result<Self, ConvertError> {
+            Ok(Shout(format!("{}{}", previous.0, "!".repeat(*params))))
+        }
     }
-}
 
-impl<T: 'static> std::ops::Deref for ArcHandle<T> {
-    type Target = T;
+    #[test]
+    fn pipelines_cache_every_stage() {
+        let mut assets = Assets::new();
+        let handle = assets.insert(Text("hey".to_string()));
 
-    fn deref(&self) -> &T {
-        self.handle.as_ref()
+        let first = assets
+            .convert_pipeline::<Shout>(handle.clone(), &(), &2)
+            .unwrap();
+        let again = assets
+            .convert_pipeline::<Shout>(handle.clone(), &(), &2)
+            .unwrap();
+        assert_eq!(first.0, "HEY!!");
+        assert!(first == again);
+
+        // other params of the last stage reuse the previous stage
+        let more = assets
+            .convert_pipeline::<Shout>(handle.clone(), &(), &3)
+            .unwrap();
+        assert_eq!(more.0, "HEY!!!");
+        assert_eq!(assets.convert_count::<Upper>(&handle), Some(1));
+
+        // changing the source rebuilds the whole chain
+        assets.get_mut(handle.clone()).unwrap().0 = "ho".to_string();
+        let rebuilt = assets
+            .convert_pipeline::<Shout>(handle.clone(), &(), &2)
+            .unwrap();
+        assert_eq!(rebuilt.0, "HO!!");
+        assert_eq!(assets.convert_count::<Upper>(&handle), Some(2));
     }
-}
 
-impl<T: 'static> AsRef<T> for ArcHandle<T> {
-    fn as_ref(&self) -> &T {
-        self.handle.as_ref()
+    #[test]
+    fn load_glob_loads_only_matching_files() {
+        let dir = temp_dir();
+        fs::create_dir_all(dir.join("shaders/post")).unwrap();
+        fs::write(dir.join("shaders/a.wgsl"), "a").unwrap();
+        fs::write(dir.join("shaders/post/b.wgsl"), "b").unwrap();
+        fs::write(dir.join("shaders/notes.txt"), "notes").unwrap();
+        let mut assets = Assets::new();
+        assets.set_root(&dir);
+
+        let handles = assets
+            .load_glob::<Text>(
+                "shaders/**/*.wgsl",
+                LoadOptions {
+                    sync: true,
+                    watch: true,
+                    write: false,
+                },
+            )
+            .unwrap();
+        let values: Vec<&str> = handles
+            .iter()
+            .map(|handle| assets.get(handle.clone()).unwrap().0.as_str())
+            .collect();
+        assert_eq!(values, ["a", "b"]);
+        assert_eq!(assets.stats().watched_paths, 2);
     }
-}
 
-// any stuff
+    #[test]
+    fn load_glob_fails_for_missing_directories() {
+        let mut assets = Assets::new();
+        assets.set_root(&temp_dir());
 
-impl<T: Any + Send + Sync + 'static> ArcHandle<T> {
-    pub fn upcast(self) -> ArcHandle<dyn Any + Send + Sync> {
-        ArcHandle {
-            handle: self.handle as Arc<dyn Any + Send + Sync>,
-            id: self.id,
+        let result = assets.load_glob::<Text>("missing/*.wgsl", LoadOptions::default());
+        assert!(matches!(result, Err(AssetError::Io(_))));
+    }
+
+    #[test]
+    fn simulated_delay_keeps_loads_pending() {
+        let dir = temp_dir();
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        let mut assets = Assets::new();
+        assets.set_simulated_delay(Some(Duration::from_millis(100)));
+
+        let start = Instant::now();
+        let handle = assets.load::<Text>(&dir.join("a.txt"), false);
+        assets.poll_loaded();
+        assert_eq!(assets.asset_state(handle.clone()), AssetState::Loading);
+
+        assets.flush();
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        assert_eq!(assets.get(handle).unwrap().0, "a");
+    }
+
+    #[test]
+    fn loads_run_without_delay_by_default() {
+        let dir = temp_dir();
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        let mut assets = Assets::new();
+
+        let start = Instant::now();
+        let handle = assets.load::<Text>(&dir.join("a.txt"), false);
+        assets.flush();
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(assets.get(handle).unwrap().0, "a");
+    }
+
+    // save file which gained a key in version 2 and a level in version 3
+    #[derive(Debug, PartialEq)]
+    struct Save {
+        name: String,
+        level: u32,
+    }
+
+    impl Asset for Save {}
+
+    impl VersionedAsset for Save {
+        fn current_version() -> u32 {
+            3
+        }
+
+        fn migrate(from: u32, data: Vec<u8>) -> Vec<u8> {
+            let text = String::from_utf8(data).unwrap();
+            match from {
+                1 => format!("name={}", text.trim()).into_bytes(),
+                _ => format!("{}\nlevel=1", text.trim()).into_bytes(),
+            }
+        }
+
+        fn parse(data: &[u8]) -> Result<Self, AssetLoadError> {
+            let text =
+                std::str::from_utf8(data).map_err(|err| AssetLoadError::Parse(err.to_string()))?;
+            let value = |key: &str| {
+                text.lines()
+                    .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+                    .ok_or_else(|| AssetLoadError::Parse(format!("missing {key}")))
+            };
+            Ok(Save {
+                name: value("name")?.to_string(),
+                level: value("level")?
+                    .parse()
+                    .map_err(|_| AssetLoadError::Parse("bad level".to_string()))?,
+            })
+        }
+
+        fn rewrite_migrated() -> bool {
+            true
         }
     }
-}
-impl ArcHandle<dyn Any + Sync + Send> {
-    fn downcast<G: Send + Sync>(&self) -> ArcHandle<G> {
-        ArcHandle {
-            handle: self
-                .handle
-                .clone()
-                .downcast::<G>()
-                .expect("could not downcast"),
-            id: self.id,
+
+    #[test]
+    fn version_one_files_are_migrated_and_rewritten() {
+        let dir = temp_dir();
+        let path = dir.join("save.txt");
+        fs::write(&path, "ada\n").unwrap();
+        let mut assets = Assets::new();
+
+        let handle = assets.load_versioned::<Save>(&path, true);
+        let expected = Save {
+            name: "ada".to_string(),
+            level: 1,
+        };
+        assert_eq!(assets.get(handle), Some(&expected));
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "version 3\nname=ada\nlevel=1"
+        );
+    }
+
+    #[test]
+    fn current_version_files_are_parsed_as_is() {
+        let dir = temp_dir();
+        let path = dir.join("save.txt");
+        fs::write(&path, "version 3\nname=bo\nlevel=7").unwrap();
+        let mut assets = Assets::new();
+
+        let handle = assets.load_versioned::<Save>(&path, true);
+        assert_eq!(assets.get(handle).unwrap().level, 7);
+    }
+
+    #[test]
+    fn newer_versions_fail_to_load() {
+        let dir = temp_dir();
+        let path = dir.join("save.txt");
+        fs::write(&path, "version 4\nname=bo\nlevel=7").unwrap();
+        let mut assets = Assets::new();
+
+        let handle = assets.load_versioned::<Save>(&path, true);
+        assert_eq!(assets.asset_state(handle), AssetState::Failed);
+    }
+
+    #[test]
+    fn insert_from_bytes_parses_without_a_file() {
+        let mut assets = Assets::new();
+        let handle = assets.insert_from_bytes::<Text>(b"embedded");
+
+        assert_eq!(assets.get(handle.clone()).unwrap().0, "embedded");
+        assert_eq!(assets.canonical_path(&handle), None);
+        assert_eq!(assets.stats().watched_paths, 0);
+    }
+
+    #[test]
+    fn insert_from_bytes_keeps_the_parse_error() {
+        let mut assets = Assets::new();
+        let handle = assets.insert_from_bytes::<Text>(&[0xff, 0xfe]);
+
+        assert!(assets.get(handle.clone()).is_none());
+        assert!(matches!(
+            assets.load_error(&handle),
+            Some(AssetLoadError::Parse(_))
+        ));
+    }
+
+    // in memory source whose files can change, notifying the watcher
+    #[derive(Clone, Default)]
+    struct LiveSource {
+        files: Arc<Mutex<HashMap<PathBuf, Vec<u8>>>>,
+        changed: Arc<Mutex<Option<mpsc::Sender<PathBuf>>>>,
+    }
+
+    impl LiveSource {
+        fn set(&self, path: &str, content: &str) {
+            let path = PathBuf::from(path);
+            self.files
+                .lock()
+                .unwrap()
+                .insert(path.clone(), content.as_bytes().to_vec());
+            if let Some(changed) = self.changed.lock().unwrap().as_ref() {
+                changed.send(path).unwrap();
+            }
+        }
+    }
+
+    impl AssetSource for LiveSource {
+        fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+            self.files
+                .lock()
+                .unwrap()
+                .get(path)
+                .cloned()
+                .ok_or_else(|| std::io::ErrorKind::NotFound.into())
+        }
+
+        fn watch(&self, _path: &Path, changed: mpsc::Sender<PathBuf>) -> std::io::Result<()> {
+            *self.changed.lock().unwrap() = Some(changed);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn load_source_reads_from_the_source() {
+        let source = LiveSource::default();
+        source.set("config.txt", "in memory");
+        let mut assets = Assets::new();
+        assets.set_source(Box::new(source));
+
+        let sync = assets.load_source::<Text>(Path::new("config.txt"), true);
+        assert_eq!(assets.get(sync.clone()).unwrap().0, "in memory");
+        let same = assets.load_source::<Text>(Path::new("config.txt"), false);
+        assert_eq!(same, sync);
+
+        let missing = assets.load_source::<Text>(Path::new("missing.txt"), false);
+        assets.flush();
+        assert_eq!(assets.asset_state(missing), AssetState::Failed);
+    }
+
+    #[test]
+    fn source_changes_reload_watched_assets() {
+        let source = LiveSource::default();
+        source.set("config.txt", "old");
+        let mut assets = Assets::new();
+        assets.set_source(Box::new(source.clone()));
+        let handle = assets.load_source_watch::<Text>(Path::new("config.txt"), true);
+
+        source.set("config.txt", "new");
+        assets.poll_reload();
+        assert_eq!(assets.get(handle).unwrap().0, "new");
+    }
+
+    #[test]
+    fn writes_of_shared_assets_use_a_copy() {
+        let dir = temp_dir();
+        let path = dir.join("a.txt");
+        fs::write(&path, "old").unwrap();
+        let mut assets = Assets::new();
+        assets.register_copy_on_write::<Text>();
+        let handle = assets.load_write::<Text>(&path, true);
+
+        assets.get_mut(handle.clone()).unwrap().0 = "new".to_string();
+        let arc = assets.get_arc(&handle).unwrap();
+        assets.poll_write();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(arc.0, "new");
+        assert!(assets.write_error(&handle).is_none());
+    }
+
+    #[test]
+    fn writes_of_shared_assets_wait_without_copy_on_write() {
+        let dir = temp_dir();
+        let path = dir.join("a.txt");
+        fs::write(&path, "old").unwrap();
+        let mut assets = Assets::new();
+        let handle = assets.load_write::<Text>(&path, true);
+
+        assets.get_mut(handle.clone()).unwrap().0 = "new".to_string();
+        let arc = assets.get_arc(&handle).unwrap();
+        assets.poll_write();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+        assert!(assets.write_error(&handle).is_some());
+
+        drop(arc);
+        assets.poll_write();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert!(assets.write_error(&handle).is_none());
+    }
+
+    #[test]
+    fn arcs_held_before_get_mut_keep_the_old_value() {
+        let dir = temp_dir();
+        let path = dir.join("a.txt");
+        fs::write(&path, "old").unwrap();
+        let mut assets = Assets::new();
+        assets.register_copy_on_write::<Text>();
+        let handle = assets.load_write::<Text>(&path, true);
+
+        let arc = assets.get_arc(&handle).unwrap();
+        assets.get_mut(handle.clone()).unwrap().0 = "new".to_string();
+        assets.poll_write();
+        assert_eq!(arc.0, "old");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+    }
+
+    #[test]
+    fn load_first_existing_loads_and_watches_the_first_existing_path() {
+        let dir = temp_dir();
+        let override_path = dir.join("mod.txt");
+        let base_path = dir.join("base.txt");
+        fs::write(&base_path, "base").unwrap();
+        let mut assets = Assets::new();
+
+        let handle = assets
+            .load_first_existing::<Text>(
+                &[&override_path, &base_path],
+                LoadOptions {
+                    sync: true,
+                    watch: true,
+                    write: false,
+                },
+            )
+            .unwrap();
+        assert_eq!(assets.get(handle.clone()).unwrap().0, "base");
+        let canonical = fs::canonicalize(&base_path).unwrap();
+        assert_eq!(assets.canonical_path(&handle), Some(canonical.as_path()));
+
+        fs::write(&base_path, "edited").unwrap();
+        assets.force_reload(canonical);
+        assets.poll_reload();
+        assert_eq!(assets.get(handle).unwrap().0, "edited");
+    }
+
+    #[test]
+    fn load_first_existing_fails_if_no_path_exists() {
+        let dir = temp_dir();
+        let mut assets = Assets::new();
+
+        let result = assets.load_first_existing::<Text>(
+            &[&dir.join("mod.txt"), &dir.join("base.txt")],
+            LoadOptions::default(),
+        );
+        assert!(matches!(result, Err(AssetError::NotFound(path)) if path == dir.join("mod.txt")));
+    }
+
+    #[test]
+    fn get_many_returns_assets_in_handle_order() {
+        let mut assets = Assets::new();
+        let a = assets.insert(Text("a".to_string()));
+        let b = assets.insert(Text("b".to_string()));
+        let removed = assets.insert(Text("removed".to_string()));
+        assets.remove(removed.clone());
+
+        let values: Vec<Option<&str>> = assets
+            .get_many(&[b, removed, a])
+            .into_iter()
+            .map(|text| text.map(|text| text.0.as_str()))
+            .collect();
+        assert_eq!(values, [Some("b"), None, Some("a")]);
+    }
+
+    // asset recording the threads it was loaded on
+    struct ThreadProbe;
+
+    static PROBE_THREADS: Mutex<Vec<std::thread::ThreadId>> = Mutex::new(Vec::new());
+
+    impl Asset for ThreadProbe {}
+
+    impl LoadableAsset for ThreadProbe {
+        fn load(_path: &Path) -> Result<Self, AssetLoadError> {
+            PROBE_THREADS
+                .lock()
+                .unwrap()
+                .push(std::thread::current().id());
+            Ok(ThreadProbe)
+        }
+    }
+
+    #[test]
+    fn loads_run_on_at_most_the_worker_threads() {
+        let dir = temp_dir();
+        let mut assets = Assets::with_workers(2);
+        let handles: Vec<AssetHandle<ThreadProbe>> = (0..50)
+            .map(|i| {
+                let path = dir.join(i.to_string());
+                fs::write(&path, "").unwrap();
+                assets.load(&path, false)
+            })
+            .collect();
+
+        assets.flush();
+        assert!(
+            handles
+                .iter()
+                .all(|handle| assets.get(handle.clone()).is_some())
+        );
+        let threads: HashSet<_> = PROBE_THREADS.lock().unwrap().iter().copied().collect();
+        assert_eq!(PROBE_THREADS.lock().unwrap().len(), 50);
+        assert!(threads.len() <= 2);
+    }
+
+    #[test]
+    fn loads_and_reloads_read_through_the_source() {
+        let source = LiveSource::default();
+        source.set("config.txt", "old");
+        source.set("other.txt", "other");
+        let mut assets = Assets::with_source(Box::new(source.clone()));
+
+        let handle = assets.load_watch::<Text>(Path::new("config.txt"), true);
+        let other = assets.load::<Text>(Path::new("other.txt"), false);
+        assets.flush();
+        assert_eq!(assets.get(handle.clone()).unwrap().0, "old");
+        assert_eq!(assets.get(other).unwrap().0, "other");
+        assert_eq!(handle.path(), Some(Path::new("config.txt")));
+
+        source.set("config.txt", "new");
+        assets.poll_reload();
+        assert_eq!(assets.get(handle).unwrap().0, "new");
+
+        let missing = assets.load::<Text>(Path::new("missing.txt"), true);
+        assert_eq!(assets.asset_state(missing), AssetState::Failed);
+    }
+
+    // run a future on the current thread, parking until it is woken
+    #[cfg(feature = "async")]
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        struct Unpark(std::thread::Thread);
+
+        impl std::task::Wake for Unpark {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = Arc::new(Unpark(std::thread::current())).into();
+        let mut context = std::task::Context::from_waker(&waker);
+        let mut future = std::pin::pin!(future);
+        loop {
+            if let std::task::Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+            std::thread::park();
+        }
+    }
+
+    // asset recording how many of its loads ran at the same time, awaited or not
+    #[cfg(feature = "async")]
+    struct AwaitProbe;
+
+    #[cfg(feature = "async")]
+    static AWAIT_PROBES_RUNNING: AtomicUsize = AtomicUsize::new(0);
+    #[cfg(feature = "async")]
+    static AWAIT_PROBES_MAX: AtomicUsize = AtomicUsize::new(0);
+
+    #[cfg(feature = "async")]
+    impl Asset for AwaitProbe {}
+
+    #[cfg(feature = "async")]
+    impl LoadableAsset for AwaitProbe {
+        fn load(_path: &Path) -> Result<Self, AssetLoadError> {
+            let running = AWAIT_PROBES_RUNNING.fetch_add(1, SeqCst) + 1;
+            AWAIT_PROBES_MAX.fetch_max(running, SeqCst);
+            std::thread::sleep(Duration::from_millis(30));
+            AWAIT_PROBES_RUNNING.fetch_sub(1, SeqCst);
+            Ok(AwaitProbe)
         }
     }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn load_async_await_counts_towards_the_load_concurrency() {
+        let dir = temp_dir();
+        let mut assets = Assets::with_workers(4);
+        assets.set_load_concurrency(1);
+        let paths: Vec<PathBuf> = (0..3)
+            .map(|i| {
+                let path = dir.join(format!("{}.probe", i));
+                fs::write(&path, "").unwrap();
+                path
+            })
+            .collect();
+
+        let polled: Vec<AssetHandle<AwaitProbe>> = paths[..2]
+            .iter()
+            .map(|path| assets.load(path, false))
+            .collect();
+        let awaited = block_on(assets.load_async_await::<AwaitProbe>(&paths[2])).unwrap();
+        assets.flush();
+        assert!(assets.get(awaited).is_some());
+        assert!(
+            polled
+                .into_iter()
+                .all(|handle| assets.get(handle).is_some())
+        );
+        assert_eq!(AWAIT_PROBES_MAX.load(SeqCst), 1);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn load_async_await_is_pending_for_loads_of_the_same_file() {
+        let dir = temp_dir();
+        let path = dir.join("a.txt");
+        fs::write(&path, "a").unwrap();
+        let mut assets = Assets::new();
+        assets.set_simulated_delay(Some(Duration::from_millis(50)));
+
+        // start the awaited load and drop it before it completes
+        let mut context = std::task::Context::from_waker(std::task::Waker::noop());
+        let mut future = Box::pin(assets.load_async_await::<Text>(&path));
+        assert!(future.as_mut().poll(&mut context).is_pending());
+        drop(future);
+
+        let handle = assets.load::<Text>(&path, false);
+        assert!(assets.is_pending(handle.clone()));
+        assert_eq!(assets.load_pending.len(), 1);
+        assets.flush();
+        assert_eq!(assets.get(handle).unwrap().0, "a");
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn load_async_await_errors_name_the_file() {
+        let dir = temp_dir();
+        let path = dir.join("not_utf8.txt");
+        fs::write(&path, [0xff, 0xfe]).unwrap();
+        let mut assets = Assets::new();
+
+        let err = block_on(assets.load_async_await::<Text>(&path)).unwrap_err();
+        assert_eq!(err.path(), Some(fs::canonicalize(&path).unwrap().as_path()));
+    }
 }
@@ -0,0 +1,265 @@
+use crate::{assets::DynAsset, handle::AssetHandle};
+use notify_debouncer_mini::notify;
+use std::{
+    collections::HashMap,
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug)]
+pub enum AssetError {
+    /// The handle does not point to a loaded asset
+    NotLoaded,
+    /// The asset is shared with other handles and can not be mutated
+    Shared,
+    /// The asset is frozen and can not be mutated
+    Frozen,
+    /// The file does not exist
+    NotFound(PathBuf),
+    Io(io::Error),
+    /// No type is registered under the name
+    UnknownType(String),
+    /// The type is registered without write support
+    NotWriteable(String),
+    Parse(String),
+    /// Some manifest entries failed, the rest are loaded
+    Manifest {
+        handles: HashMap<String, AssetHandle<DynAsset>>,
+        errors: Vec<(usize, AssetError)>,
+    },
+    /// Some directories matching a glob could not be read, the files found elsewhere are loaded
+    Glob {
+        handles: Vec<AssetHandle<DynAsset>>,
+        errors: Vec<(PathBuf, io::Error)>,
+    },
+}
+
+/// Error returned by a loader when a file can not be turned into an asset
+#[derive(Debug)]
+pub enum AssetLoadError {
+    Io(io::Error),
+    Parse(String),
+    /// Parse error at a line and column, both counted from 1
+    ParseAt {
+        line: u32,
+        column: u32,
+        message: String,
+    },
+    /// The file does not exist
+    NotFound(PathBuf),
+    /// The load was cancelled before it started
+    Cancelled,
+    /// The loader panicked
+    Panicked,
+    /// Error of a loader together with the file it was loading
+    File {
+        path: PathBuf,
+        source: Box<AssetLoadError>,
+    },
+}
+
+impl AssetLoadError {
+    /// Attach the path of the file being loaded, Assets does this for loader errors
+    pub fn at_path(self, path: &Path) -> Self {
+        match self {
+            AssetLoadError::NotFound(_)
+            | AssetLoadError::Cancelled
+            | AssetLoadError::File { .. } => self,
+            err => AssetLoadError::File {
+                path: path.to_path_buf(),
+                source: Box::new(err),
+            },
+        }
+    }
+
+    /// Get the file the error occurred in, if known
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            AssetLoadError::NotFound(path) | AssetLoadError::File { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Get the line and column the error occurred at, if the loader reported them
+    pub fn location(&self) -> Option<(u32, u32)> {
+        match self {
+            AssetLoadError::ParseAt { line, column, .. } => Some((*line, *column)),
+            AssetLoadError::File { source, .. } => source.location(),
+            _ => None,
+        }
+    }
+}
+
+/// Error returned when a source can not be converted into a render asset,
+/// e.g. a shader which does not compile
+#[derive(Debug)]
+pub struct ConvertError(pub String);
+
+/// Error of the file watcher, collected by [`crate::Assets::watch_errors`]
+#[derive(Debug)]
+pub enum WatchError {
+    /// The watcher could not be created
+    Create(notify::Error),
+    /// The path could not be watched, its asset is not hot reloaded
+    Watch(PathBuf, notify::Error),
+    /// The watcher reported an error while running
+    Event(notify::Error),
+}
+
+/// Why [`crate::Assets::try_get`] has no asset for a handle
+#[derive(Debug)]
+pub enum GetError<'a> {
+    /// The async load has not been delivered by poll_loaded yet
+    Loading,
+    /// The loader returned an error
+    Failed(&'a AssetLoadError),
+    /// The asset was removed or never loaded
+    Missing,
+    /// The handle was retyped and points to an asset of another type
+    TypeMismatch { expected: &'static str },
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::NotLoaded => write!(f, "asset is not loaded"),
+            AssetError::Shared => write!(f, "asset is shared with other handles"),
+            AssetError::Frozen => write!(f, "asset is frozen"),
+            AssetError::NotFound(path) => write!(f, "file not found: {}", path.display()),
+            AssetError::Io(err) => write!(f, "io error: {}", err),
+            AssetError::UnknownType(name) => write!(f, "unknown asset type: {}", name),
+            AssetError::NotWriteable(name) => write!(f, "asset type is not writeable: {}", name),
+            AssetError::Parse(message) => write!(f, "parse error: {}", message),
+            AssetError::Manifest { errors, .. } => {
+                write!(f, "{} manifest entries failed", errors.len())?;
+                for (line, err) in errors {
+                    write!(f, "\n  line {}: {}", line, err)?;
+                }
+                Ok(())
+            }
+            AssetError::Glob { errors, .. } => {
+                write!(f, "{} directories could not be read", errors.len())?;
+                for (path, err) in errors {
+                    write!(f, "\n  {}: {}", path.display(), err)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for AssetError {}
+
+impl From<io::Error> for AssetError {
+    fn from(err: io::Error) -> Self {
+        AssetError::Io(err)
+    }
+}
+
+impl fmt::Display for AssetLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetLoadError::Io(err) => write!(f, "io error: {}", err),
+            AssetLoadError::Parse(message) => write!(f, "parse error: {}", message),
+            AssetLoadError::ParseAt {
+                line,
+                column,
+                message,
+            } => write!(f, "parse error at {}:{}: {}", line, column, message),
+            AssetLoadError::NotFound(path) => write!(f, "file not found: {}", path.display()),
+            AssetLoadError::Cancelled => write!(f, "load cancelled"),
+            AssetLoadError::Panicked => write!(f, "loader panicked"),
+            // reads like compiler output, e.g. `failed to load assets/alice.person:2:5: invalid integer`
+            AssetLoadError::File { path, source } => match source.as_ref() {
+                AssetLoadError::ParseAt {
+                    line,
+                    column,
+                    message,
+                } => write!(
+                    f,
+                    "failed to load {}:{}:{}: {}",
+                    path.display(),
+                    line,
+                    column,
+                    message
+                ),
+                source => write!(f, "failed to load {}: {}", path.display(), source),
+            },
+        }
+    }
+}
+
+impl std::error::Error for AssetLoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AssetLoadError::Io(err) => Some(err),
+            AssetLoadError::File { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "convert error: {}", self.0)
+    }
+}
+
+impl std::error::Error for ConvertError {}
+
+impl fmt::Display for WatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatchError::Create(err) => write!(f, "could not create watcher: {}", err),
+            WatchError::Watch(path, err) => {
+                write!(f, "could not watch {}: {}", path.display(), err)
+            }
+            WatchError::Event(err) => write!(f, "watcher error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for WatchError {}
+
+impl fmt::Display for GetError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GetError::Loading => write!(f, "asset is still loading"),
+            GetError::Failed(err) => write!(f, "asset failed to load: {}", err),
+            GetError::Missing => write!(f, "asset is missing"),
+            GetError::TypeMismatch { expected } => {
+                write!(f, "handle does not point to a {}", expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GetError<'_> {}
+
+impl From<io::Error> for AssetLoadError {
+    fn from(err: io::Error) -> Self {
+        AssetLoadError::Io(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn at_path_wraps_an_error_once() {
+        let err = AssetLoadError::Parse("bad".to_string())
+            .at_path(Path::new("a.txt"))
+            .at_path(Path::new("b.txt"));
+        assert_eq!(err.path(), Some(Path::new("a.txt")));
+        assert_eq!(err.location(), None);
+        assert_eq!(err.to_string(), "failed to load a.txt: parse error: bad");
+    }
+
+    #[test]
+    fn not_found_keeps_its_own_path() {
+        let err =
+            AssetLoadError::NotFound(PathBuf::from("missing.txt")).at_path(Path::new("other.txt"));
+        assert_eq!(err.path(), Some(Path::new("missing.txt")));
+    }
+}
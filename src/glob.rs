@@ -0,0 +1,215 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Files matching a pattern, together with the directories below the base which could
+/// not be read
+pub(crate) struct Found {
+    pub(crate) files: Vec<PathBuf>,
+    pub(crate) errors: Vec<(PathBuf, io::Error)>,
+}
+
+/// Find the files matching a pattern, sorted by path
+///
+/// `*` matches any part of a file or directory name, `?` a single character and `**`
+/// any number of directories. Patterns are relative to `root`, directories are not returned.
+/// Only directories the pattern can match are searched. Fails only if the deepest
+/// directory without wildcards can not be read
+pub(crate) fn find(root: &Path, pattern: &str) -> io::Result<Found> {
+    let components: Vec<&str> = pattern.split('/').filter(|c| !c.is_empty()).collect();
+
+    // walk from the deepest directory without wildcards
+    let literal = components
+        .iter()
+        .take_while(|component| !component.contains(['*', '?']))
+        .count()
+        .min(components.len().saturating_sub(1));
+    let mut base = root.to_path_buf();
+    if pattern.starts_with('/') {
+        base = PathBuf::from("/");
+    }
+    base.extend(&components[..literal]);
+
+    let mut found = Found {
+        files: Vec::new(),
+        errors: Vec::new(),
+    };
+    let entries = fs::read_dir(&base)?;
+    walk(
+        &base,
+        entries,
+        &mut Vec::new(),
+        &components[literal..],
+        &mut found,
+    );
+    found.files.sort();
+    Ok(found)
+}
+
+fn walk(
+    dir: &Path,
+    entries: fs::ReadDir,
+    relative: &mut Vec<String>,
+    pattern: &[&str],
+    found: &mut Found,
+) {
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                found.errors.push((dir.to_path_buf(), err));
+                continue;
+            }
+        };
+        relative.push(entry.file_name().to_string_lossy().into_owned());
+        match entry.file_type() {
+            Ok(ty) if ty.is_dir() => {
+                if may_contain(pattern, relative) {
+                    match fs::read_dir(entry.path()) {
+                        Ok(entries) => walk(&entry.path(), entries, relative, pattern, found),
+                        Err(err) => found.errors.push((entry.path(), err)),
+                    }
+                }
+            }
+            Ok(_) => {
+                if matches(pattern, relative) {
+                    found.files.push(entry.path());
+                }
+            }
+            Err(err) => found.errors.push((entry.path(), err)),
+        }
+        relative.pop();
+    }
+}
+
+// check if files inside a directory can match, so directories which can not are skipped
+fn may_contain(pattern: &[&str], dir: &[String]) -> bool {
+    match (pattern.first(), dir.first()) {
+        (Some(&"**"), _) => true,
+        (Some(_), None) => true,
+        (Some(component), Some(name)) => {
+            let component: Vec<char> = component.chars().collect();
+            let name: Vec<char> = name.chars().collect();
+            matches_name(&component, &name) && may_contain(&pattern[1..], &dir[1..])
+        }
+        (None, _) => false,
+    }
+}
+
+fn matches(pattern: &[&str], path: &[String]) -> bool {
+    match (pattern.first(), path.first()) {
+        (None, None) => true,
+        (Some(&"**"), _) => {
+            matches(&pattern[1..], path) || (!path.is_empty() && matches(pattern, &path[1..]))
+        }
+        (Some(component), Some(name)) => {
+            let component: Vec<char> = component.chars().collect();
+            let name: Vec<char> = name.chars().collect();
+            matches_name(&component, &name) && matches(&pattern[1..], &path[1..])
+        }
+        _ => false,
+    }
+}
+
+fn matches_name(pattern: &[char], name: &[char]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some('*'), _) => {
+            matches_name(&pattern[1..], name)
+                || (!name.is_empty() && matches_name(pattern, &name[1..]))
+        }
+        (Some('?'), Some(_)) => matches_name(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) => p == n && matches_name(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glob_matches(pattern: &str, path: &str) -> bool {
+        let pattern: Vec<&str> = pattern.split('/').collect();
+        let path: Vec<String> = path.split('/').map(str::to_string).collect();
+        matches(&pattern, &path)
+    }
+
+    #[test]
+    fn wildcards_match_within_names() {
+        assert!(glob_matches("*.wgsl", "water.wgsl"));
+        assert!(glob_matches("w?ter.*", "water.wgsl"));
+        assert!(!glob_matches("*.wgsl", "water.glsl"));
+        assert!(!glob_matches("*.wgsl", "sea/water.wgsl"));
+    }
+
+    #[test]
+    fn double_star_matches_any_number_of_directories() {
+        assert!(glob_matches("**/*.wgsl", "water.wgsl"));
+        assert!(glob_matches("**/*.wgsl", "sea/deep/water.wgsl"));
+        assert!(glob_matches("sea/**/water.wgsl", "sea/water.wgsl"));
+        assert!(!glob_matches("sea/**/water.wgsl", "lake/water.wgsl"));
+    }
+
+    #[test]
+    fn find_returns_matching_files_sorted() {
+        let root = std::env::temp_dir().join(format!("assets-glob-{}", std::process::id()));
+        fs::create_dir_all(root.join("shaders/post")).unwrap();
+        for file in [
+            "shaders/b.wgsl",
+            "shaders/a.wgsl",
+            "shaders/post/c.wgsl",
+            "shaders/d.txt",
+        ] {
+            fs::write(root.join(file), "").unwrap();
+        }
+
+        let found = find(&root, "shaders/**/*.wgsl").unwrap();
+        let expected: Vec<PathBuf> = ["shaders/a.wgsl", "shaders/b.wgsl", "shaders/post/c.wgsl"]
+            .iter()
+            .map(|file| root.join(file))
+            .collect();
+        assert_eq!(found.files, expected);
+        assert!(found.errors.is_empty());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    fn dir_may_contain(pattern: &str, dir: &str) -> bool {
+        let pattern: Vec<&str> = pattern.split('/').collect();
+        let dir: Vec<String> = dir.split('/').map(str::to_string).collect();
+        may_contain(&pattern, &dir)
+    }
+
+    #[test]
+    fn only_directories_the_pattern_can_match_are_searched() {
+        assert!(dir_may_contain("shaders/*.wgsl", "shaders"));
+        assert!(!dir_may_contain("shaders/*.wgsl", "target"));
+        assert!(!dir_may_contain("shaders/*.wgsl", "shaders/post"));
+        assert!(dir_may_contain("s*/post/*.wgsl", "shaders/post"));
+        assert!(dir_may_contain("**/*.wgsl", "target/debug"));
+        assert!(dir_may_contain("shaders/**/*.wgsl", "shaders/post/deep"));
+        assert!(!dir_may_contain("shaders/**/*.wgsl", "target"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unreadable_directories_are_reported_and_skipped() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = std::env::temp_dir().join(format!("assets-glob-locked-{}", std::process::id()));
+        fs::create_dir_all(root.join("locked")).unwrap();
+        fs::create_dir_all(root.join("open")).unwrap();
+        fs::write(root.join("open/a.wgsl"), "").unwrap();
+        fs::set_permissions(root.join("locked"), fs::Permissions::from_mode(0o000)).unwrap();
+
+        // permissions do not apply to root
+        if fs::read_dir(root.join("locked")).is_err() {
+            let found = find(&root, "*/*.wgsl").unwrap();
+            assert_eq!(found.files, [root.join("open/a.wgsl")]);
+            assert_eq!(found.errors.len(), 1);
+            assert_eq!(found.errors[0].0, root.join("locked"));
+        }
+        fs::set_permissions(root.join("locked"), fs::Permissions::from_mode(0o755)).unwrap();
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -1,13 +1,54 @@
-use std::{any::TypeId, marker::PhantomData, path::PathBuf, sync::atomic::AtomicU64};
+use crate::assets::DynAsset;
+use std::{
+    any::TypeId,
+    marker::PhantomData,
+    path::Path,
+    sync::{Arc, atomic::AtomicU64, mpsc},
+};
 
 static NEXT_ID: AtomicU64 = AtomicU64::new(0);
 
-// TODO: should have type aswell
-#[derive(Debug)]
 pub struct AssetHandle<T: 'static> {
     pub(crate) id: u64,
     pub(crate) ty_id: TypeId,
     pub(crate) ty: PhantomData<T>,
+    // canonicalized path the asset is loaded from, None for inserted assets
+    pub(crate) path: Option<Arc<Path>>,
+    // shared by every clone that keeps the asset alive, None for handles used internally
+    pub(crate) refs: Option<Arc<HandleRef>>,
+}
+
+/// Reference count of an asset, notifies [`crate::Assets`] once the last handle is dropped
+pub(crate) struct HandleRef {
+    id: u64,
+    ty_id: TypeId,
+    dropped: mpsc::Sender<AssetHandle<DynAsset>>,
+}
+
+impl HandleRef {
+    pub(crate) fn new<T: 'static>(
+        handle: &AssetHandle<T>,
+        dropped: mpsc::Sender<AssetHandle<DynAsset>>,
+    ) -> Self {
+        Self {
+            id: handle.id,
+            ty_id: handle.ty_id,
+            dropped,
+        }
+    }
+}
+
+impl Drop for HandleRef {
+    fn drop(&mut self) {
+        // assets may already be dropped
+        let _ = self.dropped.send(AssetHandle {
+            id: self.id,
+            ty_id: self.ty_id,
+            ty: PhantomData,
+            path: None,
+            refs: None,
+        });
+    }
 }
 
 impl<T: 'static> AssetHandle<T> {
@@ -17,6 +58,20 @@ impl<T: 'static> AssetHandle<T> {
             id: NEXT_ID.fetch_add(1, std::sync::atomic::Ordering::SeqCst),
             ty_id: TypeId::of::<T>(),
             ty: PhantomData,
+            path: None,
+            refs: None,
+        }
+    }
+
+    /// Create a handle with an explicit id, later handles will not reuse it
+    pub(crate) fn with_id(id: u64) -> Self {
+        NEXT_ID.fetch_max(id.saturating_add(1), std::sync::atomic::Ordering::SeqCst);
+        Self {
+            id,
+            ty_id: TypeId::of::<T>(),
+            ty: PhantomData,
+            path: None,
+            refs: None,
         }
     }
 
@@ -25,18 +80,80 @@ impl<T: 'static> AssetHandle<T> {
         self.id
     }
 
+    /// Retype the handle, keeping the type id of the asset it points to
+    ///
+    /// The new handle does not keep the asset alive
     pub(crate) fn clone_typed<G>(&self) -> AssetHandle<G> {
         AssetHandle::<G> {
             id: self.id,
             ty: PhantomData,
-            ty_id: TypeId::of::<T>(),
+            ty_id: self.ty_id,
+            path: self.path.clone(),
+            refs: None,
+        }
+    }
+
+    /// Set the path the asset is loaded from
+    pub(crate) fn with_path(self, path: &Path) -> Self {
+        Self {
+            path: Some(Arc::from(path)),
+            ..self
+        }
+    }
+
+    // retype the handle, keeping the asset alive as long as self would
+    fn retyped<G>(&self) -> AssetHandle<G> {
+        AssetHandle::<G> {
+            refs: self.refs.clone(),
+            ..self.clone_typed()
+        }
+    }
+
+    /// Get the canonicalized path the asset is loaded from, None for inserted assets
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Erase the asset type, for apis taking handles of mixed types
+    pub fn erase(&self) -> AssetHandle<DynAsset> {
+        self.retyped()
+    }
+
+    /// Create a weak handle which does not keep the asset alive
+    pub fn downgrade(&self) -> WeakAssetHandle<T> {
+        WeakAssetHandle {
+            id: self.id,
+            ty_id: self.ty_id,
+            ty: PhantomData,
+        }
+    }
+}
+
+impl AssetHandle<DynAsset> {
+    /// Create a handle to an asset whose type is only known at runtime
+    pub(crate) fn new_erased(ty_id: TypeId) -> Self {
+        Self {
+            ty_id,
+            ..Self::new()
         }
     }
+
+    /// Get the typed handle back, None if the asset is of another type
+    pub fn downcast<T: 'static>(&self) -> Option<AssetHandle<T>> {
+        (self.ty_id == TypeId::of::<T>()).then(|| self.retyped())
+    }
+}
+
+/// Check if two handles point to the same asset
+///
+/// Unlike comparing erased handles by id, this also requires the asset types to match
+pub fn same_asset<A: 'static, B: 'static>(a: &AssetHandle<A>, b: &AssetHandle<B>) -> bool {
+    a.id() == b.id() && a.ty_id == b.ty_id
 }
 
 impl<T: 'static> PartialEq for AssetHandle<T> {
     fn eq(&self, other: &Self) -> bool {
-        self.id == other.id
+        same_asset(self, other)
     }
 }
 
@@ -48,12 +165,145 @@ impl<T: 'static> std::hash::Hash for AssetHandle<T> {
     }
 }
 
+/// Identity of a handle, so maps keyed by erased handles can be searched with a handle of
+/// any type without retyping it
+pub(crate) trait HandleKey {
+    fn key(&self) -> (u64, TypeId);
+}
+
+impl<T: 'static> HandleKey for AssetHandle<T> {
+    fn key(&self) -> (u64, TypeId) {
+        (self.id, self.ty_id)
+    }
+}
+
+// hashed and compared like AssetHandle, as Borrow requires
+impl std::hash::Hash for dyn HandleKey + '_ {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.key().0.hash(state);
+    }
+}
+
+impl PartialEq for dyn HandleKey + '_ {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for dyn HandleKey + '_ {}
+
+impl<'a> std::borrow::Borrow<dyn HandleKey + 'a> for AssetHandle<DynAsset> {
+    fn borrow(&self) -> &(dyn HandleKey + 'a) {
+        self
+    }
+}
+
+impl<T: 'static> std::fmt::Debug for AssetHandle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AssetHandle")
+            .field("id", &self.id)
+            .field("ty_id", &self.ty_id)
+            .finish()
+    }
+}
+
 impl<T: 'static> Clone for AssetHandle<T> {
+    fn clone(&self) -> Self {
+        self.retyped()
+    }
+}
+
+/// Handle which can be stored without keeping the asset alive
+///
+/// Lookups through [`crate::Assets::get_weak`] return None once the asset is removed
+pub struct WeakAssetHandle<T: 'static> {
+    pub(crate) id: u64,
+    pub(crate) ty_id: TypeId,
+    pub(crate) ty: PhantomData<T>,
+}
+
+impl<T: 'static> WeakAssetHandle<T> {
+    pub(crate) fn clone_strong(&self) -> AssetHandle<T> {
+        AssetHandle {
+            id: self.id,
+            ty_id: self.ty_id,
+            ty: PhantomData,
+            path: None,
+            refs: None,
+        }
+    }
+}
+
+impl<T: 'static> PartialEq for WeakAssetHandle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id && self.ty_id == other.ty_id
+    }
+}
+
+impl<T: 'static> Eq for WeakAssetHandle<T> {}
+
+impl<T: 'static> std::hash::Hash for WeakAssetHandle<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl<T: 'static> std::fmt::Debug for WeakAssetHandle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WeakAssetHandle")
+            .field("id", &self.id)
+            .field("ty_id", &self.ty_id)
+            .finish()
+    }
+}
+
+impl<T: 'static> Clone for WeakAssetHandle<T> {
     fn clone(&self) -> Self {
         Self {
             id: self.id,
             ty: PhantomData,
-            ty_id: TypeId::of::<T>(),
+            ty_id: self.ty_id,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_asset_requires_the_same_type() {
+        let a = AssetHandle::<u32>::with_id(1000);
+        let b = AssetHandle::<String>::with_id(1000);
+        assert!(!same_asset(&a, &b));
+        assert!(same_asset(&a, &a.clone()));
+    }
+
+    #[test]
+    fn retyped_handles_still_match_their_asset() {
+        let a = AssetHandle::<u32>::new();
+        let erased = a.clone_typed::<DynAsset>();
+        let retyped = a.clone_typed::<String>();
+        assert!(same_asset(&a, &erased));
+        assert!(same_asset(&a, &retyped));
+        assert_eq!(erased.downcast::<u32>(), Some(a.clone()));
+        assert!(erased.downcast::<String>().is_none());
+    }
+
+    #[test]
+    fn erased_handles_of_different_types_are_not_equal() {
+        let a = AssetHandle::<u32>::with_id(2000).clone_typed::<DynAsset>();
+        let b = AssetHandle::<String>::with_id(2000).clone_typed::<DynAsset>();
+        assert_ne!(a, b);
+        assert_eq!(a, a.clone());
+    }
+
+    #[test]
+    fn maps_of_erased_handles_are_searchable_by_typed_handles() {
+        let a = AssetHandle::<u32>::with_id(3000);
+        let b = AssetHandle::<String>::with_id(3000);
+        let map = std::collections::HashMap::from([(a.clone_typed::<DynAsset>(), "a")]);
+        assert_eq!(map.get(&a as &dyn HandleKey), Some(&"a"));
+        assert_eq!(map.get(&b as &dyn HandleKey), None);
+    }
+}
@@ -0,0 +1,18 @@
+mod assets;
+mod error;
+mod glob;
+mod handle;
+mod limiter;
+#[cfg(feature = "async")]
+mod oneshot;
+mod pool;
+mod shared;
+mod snapshot;
+mod source;
+
+pub use assets::*;
+pub use error::*;
+pub use handle::*;
+pub use shared::*;
+pub use snapshot::SnapshotableAsset;
+pub use source::{AssetSource, FsSource, RangeReadableSource, read_file, read_file_to_string};
@@ -0,0 +1,173 @@
+use std::sync::{Arc, Condvar, Mutex};
+
+/// Caps how many loads run at the same time, the cap can change while loads wait
+pub(crate) struct LoadLimiter {
+    state: Mutex<LimiterState>,
+    changed: Condvar,
+}
+
+struct LimiterState {
+    running: usize,
+    limit: usize,
+}
+
+pub(crate) struct LoadPermit<'a> {
+    limiter: &'a LoadLimiter,
+}
+
+impl LoadLimiter {
+    pub(crate) fn new(limit: usize) -> Self {
+        Self {
+            state: Mutex::new(LimiterState { running: 0, limit }),
+            changed: Condvar::new(),
+        }
+    }
+
+    pub(crate) fn set_limit(&self, limit: usize) {
+        self.state.lock().expect("could not lock limiter").limit = limit;
+        self.changed.notify_all();
+    }
+
+    /// Block until fewer than `limit` loads are running
+    pub(crate) fn acquire(&self) -> LoadPermit<'_> {
+        let mut state = self.state.lock().expect("could not lock limiter");
+        while state.running >= state.limit {
+            state = self.changed.wait(state).expect("could not lock limiter");
+        }
+        state.running += 1;
+        LoadPermit { limiter: self }
+    }
+}
+
+impl Drop for LoadPermit<'_> {
+    fn drop(&mut self) {
+        self.limiter
+            .state
+            .lock()
+            .expect("could not lock limiter")
+            .running -= 1;
+        self.limiter.changed.notify_all();
+    }
+}
+
+/// Caps the estimated bytes of loads which completed but are not delivered yet
+pub(crate) struct ByteLimiter {
+    state: Mutex<ByteLimiterState>,
+    changed: Condvar,
+}
+
+struct ByteLimiterState {
+    inflight: u64,
+    limit: Option<u64>,
+}
+
+/// Bytes counted as in flight until dropped
+pub(crate) struct BytePermit {
+    limiter: Arc<ByteLimiter>,
+    bytes: u64,
+}
+
+impl ByteLimiter {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: Mutex::new(ByteLimiterState {
+                inflight: 0,
+                limit: None,
+            }),
+            changed: Condvar::new(),
+        }
+    }
+
+    pub(crate) fn set_limit(&self, limit: Option<u64>) {
+        self.state.lock().expect("could not lock limiter").limit = limit;
+        self.changed.notify_all();
+    }
+
+    pub(crate) fn inflight(&self) -> u64 {
+        self.state.lock().expect("could not lock limiter").inflight
+    }
+
+    /// Block until the bytes fit within the limit
+    ///
+    /// A load larger than the limit still runs once nothing else is in flight
+    pub(crate) fn acquire(self: &Arc<Self>, bytes: u64) -> BytePermit {
+        let mut state = self.state.lock().expect("could not lock limiter");
+        while let Some(limit) = state.limit
+            && state.inflight > 0
+            && state.inflight + bytes > limit
+        {
+            state = self.changed.wait(state).expect("could not lock limiter");
+        }
+        state.inflight += bytes;
+        BytePermit {
+            limiter: self.clone(),
+            bytes,
+        }
+    }
+}
+
+impl Drop for BytePermit {
+    fn drop(&mut self) {
+        self.limiter
+            .state
+            .lock()
+            .expect("could not lock limiter")
+            .inflight -= self.bytes;
+        self.limiter.changed.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{sync::mpsc, thread, time::Duration};
+
+    #[test]
+    fn raising_the_limit_releases_waiting_loads() {
+        let limiter = Arc::new(LoadLimiter::new(1));
+        let permit = limiter.acquire();
+
+        let (sender, receiver) = mpsc::channel();
+        let waiting = limiter.clone();
+        thread::spawn(move || {
+            let _permit = waiting.acquire();
+            sender.send(()).unwrap();
+        });
+        assert!(receiver.recv_timeout(Duration::from_millis(50)).is_err());
+
+        limiter.set_limit(2);
+        assert!(receiver.recv_timeout(Duration::from_secs(5)).is_ok());
+        drop(permit);
+    }
+
+    #[test]
+    fn bytes_wait_until_delivered_loads_are_dropped() {
+        let limiter = Arc::new(ByteLimiter::new());
+        limiter.set_limit(Some(100));
+        let permit = limiter.acquire(60);
+
+        let (sender, receiver) = mpsc::channel();
+        let waiting = limiter.clone();
+        thread::spawn(move || {
+            let permit = waiting.acquire(60);
+            sender.send(()).unwrap();
+            drop(permit);
+        });
+        assert!(receiver.recv_timeout(Duration::from_millis(50)).is_err());
+        assert_eq!(limiter.inflight(), 60);
+
+        drop(permit);
+        assert!(receiver.recv_timeout(Duration::from_secs(5)).is_ok());
+    }
+
+    #[test]
+    fn loads_larger_than_the_limit_run_alone() {
+        let limiter = Arc::new(ByteLimiter::new());
+        limiter.set_limit(Some(100));
+
+        let permit = limiter.acquire(500);
+        assert_eq!(limiter.inflight(), 500);
+        drop(permit);
+        assert_eq!(limiter.inflight(), 0);
+    }
+}
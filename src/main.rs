@@ -1,15 +1,13 @@
 use assets::{
-    ArcHandle, Asset, Assets, ConvertableRenderAsset, LoadableAsset, RenderAsset, WriteableAsset,
+    ArcHandle, Asset, AssetLoadError, Assets, ConvertError, ConvertableRenderAsset, LoadableAsset,
+    RenderAsset, WriteableAsset, read_file_to_string,
 };
-use std::{fmt::Write, fs::read_to_string, path::Path, thread::sleep, time::Duration};
-
-mod assets;
-mod handle;
+use std::{fmt::Write, path::Path, thread::sleep, time::Duration};
 
 fn main() {
     let mut assets = Assets::new();
 
-    let person1 = assets.insert(Person {
+    let _person1 = assets.insert(Person {
         name: String::from("bro"),
         age: 12,
     });
@@ -54,7 +52,7 @@ fn main() {
 }
 
 fn print_gpu_shader(shader: ArcHandle<GpuShader>) {
-    println!("{:?}", shader)
+    println!("{:?} module {}", shader, shader.module)
 }
 
 #[derive(Debug)]
@@ -65,12 +63,29 @@ struct Person {
 
 impl Asset for Person {}
 impl LoadableAsset for Person {
-    fn load(path: &Path) -> Self {
-        let inp = read_to_string(path).unwrap();
+    fn load(path: &Path) -> Result<Self, AssetLoadError> {
+        let inp = read_file_to_string(path)?;
         let mut split = inp.split_whitespace();
-        let name = split.next().unwrap().to_string();
-        let age = split.next().unwrap().parse::<u32>().unwrap();
-        Self { name, age }
+        let name = split
+            .next()
+            .ok_or_else(|| AssetLoadError::Parse("missing name".to_string()))?
+            .to_string();
+        let age = split
+            .next()
+            .ok_or_else(|| AssetLoadError::Parse("missing age".to_string()))?;
+        let age = age.parse::<u32>().map_err(|_| {
+            // point at the age so editors can jump to it
+            let offset = age.as_ptr() as usize - inp.as_ptr() as usize;
+            let before = &inp[..offset];
+            let line = before.matches('\n').count() + 1;
+            let column = before.len() - before.rfind('\n').map_or(0, |newline| newline + 1) + 1;
+            AssetLoadError::ParseAt {
+                line: line as u32,
+                column: column as u32,
+                message: "invalid integer".to_string(),
+            }
+        })?;
+        Ok(Self { name, age })
     }
 }
 impl WriteableAsset for Person {
@@ -90,9 +105,9 @@ struct Shader {
 
 impl Asset for Shader {}
 impl LoadableAsset for Shader {
-    fn load(path: &Path) -> Self {
-        let content = read_to_string(path).expect("could not read shader from disk");
-        Self { source: content }
+    fn load(path: &Path) -> Result<Self, AssetLoadError> {
+        let content = read_file_to_string(path)?;
+        Ok(Self { source: content })
     }
 }
 impl WriteableAsset for Shader {
@@ -111,8 +126,8 @@ impl ConvertableRenderAsset for GpuShader {
     type SourceAsset = Shader;
     type Params = u32;
 
-    fn convert(source: &Self::SourceAsset, params: &Self::Params) -> Self {
+    fn convert(_source: &Self::SourceAsset, _params: &Self::Params) -> Result<Self, ConvertError> {
         println!("convert shader to gpu shader");
-        Self { module: 0 }
+        Ok(Self { module: 0 })
     }
 }
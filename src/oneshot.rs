@@ -0,0 +1,129 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+type Fallback<T> = Box<dyn FnOnce(T) + Send>;
+
+struct Slot<T> {
+    value: Option<T>,
+    waker: Option<Waker>,
+    closed: bool,
+    // the receiver was dropped, values go to the fallback
+    dropped: bool,
+    fallback: Option<Fallback<T>>,
+}
+
+/// Sends a single value to the paired receiver
+pub(crate) struct Sender<T> {
+    slot: Arc<Mutex<Slot<T>>>,
+}
+
+/// Future resolving to the value sent, or None if the sender was dropped without sending
+pub(crate) struct Receiver<T> {
+    slot: Arc<Mutex<Slot<T>>>,
+}
+
+/// Channel for handing a single value to an async task, independent of the runtime
+pub(crate) fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let slot = Arc::new(Mutex::new(Slot {
+        value: None,
+        waker: None,
+        closed: false,
+        dropped: false,
+        fallback: None,
+    }));
+    (Sender { slot: slot.clone() }, Receiver { slot })
+}
+
+impl<T> Sender<T> {
+    pub(crate) fn send(self, value: T) {
+        let mut slot = self.slot.lock().expect("could not lock oneshot");
+        if !slot.dropped {
+            slot.value = Some(value);
+            return;
+        }
+        let fallback = slot.fallback.take();
+        drop(slot);
+        if let Some(fallback) = fallback {
+            fallback(value);
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut slot = self.slot.lock().expect("could not lock oneshot");
+        slot.closed = true;
+        if let Some(waker) = slot.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Hand the value to `fallback` if the receiver is dropped before receiving it
+    pub(crate) fn with_fallback(self, fallback: impl FnOnce(T) + Send + 'static) -> Self {
+        self.slot.lock().expect("could not lock oneshot").fallback = Some(Box::new(fallback));
+        self
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut slot = self.slot.lock().expect("could not lock oneshot");
+        slot.dropped = true;
+
+        // sent but never received, otherwise the fallback waits for the value
+        if let Some(value) = slot.value.take() {
+            let fallback = slot.fallback.take();
+            drop(slot);
+            if let Some(fallback) = fallback {
+                fallback(value);
+            }
+        }
+    }
+}
+
+impl<T> Future for Receiver<T> {
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut slot = self.slot.lock().expect("could not lock oneshot");
+        if let Some(value) = slot.value.take() {
+            return Poll::Ready(Some(value));
+        }
+        if slot.closed {
+            return Poll::Ready(None);
+        }
+        slot.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn values_of_dropped_receivers_go_to_the_fallback() {
+        let (fallen_back, received) = mpsc::channel();
+
+        // dropped before sending
+        let (sender, receiver) = channel();
+        let fallback = fallen_back.clone();
+        drop(receiver.with_fallback(move |value| fallback.send(value).unwrap()));
+        sender.send(1);
+
+        // dropped after sending
+        let (sender, receiver) = channel();
+        let receiver = receiver.with_fallback(move |value| fallen_back.send(value).unwrap());
+        sender.send(2);
+        drop(receiver);
+
+        assert_eq!(received.try_iter().collect::<Vec<_>>(), [1, 2]);
+    }
+}
@@ -0,0 +1,83 @@
+use std::{
+    panic::{AssertUnwindSafe, catch_unwind},
+    sync::{Arc, Mutex, mpsc},
+};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Fixed number of threads running jobs from a shared queue
+///
+/// The threads exit once the pool is dropped and the queued jobs are done.
+/// A panicking job does not take its thread down
+pub(crate) struct WorkerPool {
+    jobs: mpsc::Sender<Job>,
+}
+
+impl WorkerPool {
+    /// Start all worker threads up front
+    ///
+    /// The first load then does not wait for a thread to be created, at the
+    /// cost of idle threads for assets that never load async. A load right
+    /// after creation can still wait for the threads to be scheduled, see
+    /// `benches/first_load.rs`
+    pub(crate) fn new(workers: usize) -> Self {
+        let (jobs, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..workers.max(1) {
+            let receiver = receiver.clone();
+            std::thread::spawn(move || {
+                loop {
+                    let job = receiver.lock().expect("could not lock job queue").recv();
+                    match job {
+                        Ok(job) => {
+                            let _ = catch_unwind(AssertUnwindSafe(job));
+                        }
+                        Err(_) => break,
+                    }
+                }
+            });
+        }
+        Self { jobs }
+    }
+
+    pub(crate) fn spawn(&self, job: impl FnOnce() + Send + 'static) {
+        self.jobs.send(Box::new(job)).expect("could not queue job");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{collections::HashSet, thread, time::Duration};
+
+    #[test]
+    fn jobs_run_on_at_most_the_worker_threads() {
+        let pool = WorkerPool::new(3);
+        let threads = Arc::new(Mutex::new(HashSet::new()));
+        let (results, received) = mpsc::channel();
+        for i in 0..100 {
+            let threads = threads.clone();
+            let results = results.clone();
+            pool.spawn(move || {
+                threads.lock().unwrap().insert(thread::current().id());
+                results.send(i).unwrap();
+            });
+        }
+
+        let mut delivered: Vec<i32> = (0..100)
+            .map(|_| received.recv_timeout(Duration::from_secs(5)).unwrap())
+            .collect();
+        delivered.sort();
+        assert_eq!(delivered, (0..100).collect::<Vec<_>>());
+        assert!(threads.lock().unwrap().len() <= 3);
+    }
+
+    #[test]
+    fn panicking_jobs_keep_the_worker_alive() {
+        let pool = WorkerPool::new(1);
+        let (results, received) = mpsc::channel();
+        pool.spawn(|| panic!("job panicked"));
+        pool.spawn(move || results.send(()).unwrap());
+        assert!(received.recv_timeout(Duration::from_secs(5)).is_ok());
+    }
+}
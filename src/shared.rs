@@ -0,0 +1,99 @@
+use crate::assets::Assets;
+use std::{
+    sync::{Arc, Mutex, MutexGuard, mpsc},
+    thread::JoinHandle,
+    time::Duration,
+};
+
+/// Assets shared between threads
+pub struct SharedAssets {
+    assets: Arc<Mutex<Assets>>,
+    auto_poll: Option<(mpsc::Sender<()>, JoinHandle<()>)>,
+}
+
+impl SharedAssets {
+    pub fn new(assets: Assets) -> Self {
+        Self {
+            assets: Arc::new(Mutex::new(assets)),
+            auto_poll: None,
+        }
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, Assets> {
+        self.assets.lock().expect("could not lock assets")
+    }
+
+    /// Poll loads, reloads, conversions and writes on a background thread
+    ///
+    /// Useful when there is no frame loop driving the polls. Each poll holds the lock,
+    /// so other threads block on [`SharedAssets::lock`] while it runs
+    pub fn start_auto_poll(&mut self, interval: Duration) {
+        self.stop_auto_poll();
+
+        let (stop_sender, stop_receiver) = mpsc::channel();
+        let assets = self.assets.clone();
+        let thread = std::thread::spawn(move || {
+            while let Err(mpsc::RecvTimeoutError::Timeout) = stop_receiver.recv_timeout(interval) {
+                let mut assets = assets.lock().expect("could not lock assets");
+                assets.poll_loaded();
+                assets.poll_reload();
+                assets.poll_converted();
+                assets.poll_write();
+            }
+        });
+        self.auto_poll = Some((stop_sender, thread));
+    }
+
+    /// Stop the background polling thread and wait for it to finish
+    pub fn stop_auto_poll(&mut self) {
+        if let Some((stop_sender, thread)) = self.auto_poll.take() {
+            let _ = stop_sender.send(());
+            thread.join().expect("auto poll thread panicked");
+        }
+    }
+}
+
+impl Drop for SharedAssets {
+    fn drop(&mut self) {
+        self.stop_auto_poll();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Asset, AssetLoadError, LoadableAsset};
+    use std::{path::Path, time::Instant};
+
+    struct Text(String);
+
+    impl Asset for Text {}
+
+    impl LoadableAsset for Text {
+        fn load(path: &Path) -> Result<Self, AssetLoadError> {
+            Ok(Text(std::fs::read_to_string(path)?))
+        }
+    }
+
+    #[test]
+    fn auto_poll_finishes_loads() {
+        let path = std::env::temp_dir().join(format!("assets-auto-poll-{}", std::process::id()));
+        std::fs::write(&path, "polled").unwrap();
+        let mut shared = SharedAssets::new(Assets::new());
+        let handle = shared.lock().load::<Text>(&path, false);
+
+        shared.start_auto_poll(Duration::from_millis(5));
+        let start = Instant::now();
+        while shared.lock().get(handle.clone()).is_none() {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "load never finished"
+            );
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        shared.stop_auto_poll();
+
+        assert_eq!(shared.lock().get(handle).unwrap().0, "polled");
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,202 @@
+use crate::error::AssetError;
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const MAGIC: &[u8; 4] = b"ASNP";
+const VERSION: u64 = 1;
+
+/// Asset which can be stored in a cache snapshot
+pub trait SnapshotableAsset: Sized {
+    fn to_snapshot(&self) -> Vec<u8>;
+    /// None if the bytes are not a valid snapshot of the asset
+    fn from_snapshot(bytes: &[u8]) -> Option<Self>;
+}
+
+/// One asset stored in a snapshot
+pub(crate) struct SnapshotEntry {
+    pub(crate) ty: String,
+    pub(crate) id: u64,
+    pub(crate) path: Option<PathBuf>,
+    pub(crate) modified: Option<SystemTime>,
+    pub(crate) data: Vec<u8>,
+}
+
+pub(crate) fn encode(entries: &[SnapshotEntry]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(MAGIC);
+    put_u64(&mut bytes, VERSION);
+    put_u64(&mut bytes, entries.len() as u64);
+    for entry in entries {
+        put_bytes(&mut bytes, entry.ty.as_bytes());
+        put_u64(&mut bytes, entry.id);
+        put_bytes(&mut bytes, &path_bytes(entry.path.as_deref()));
+        match entry
+            .modified
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        {
+            Some(time) => {
+                bytes.push(1);
+                put_u64(&mut bytes, time.as_secs());
+                put_u64(&mut bytes, time.subsec_nanos() as u64);
+            }
+            None => bytes.push(0),
+        }
+        put_bytes(&mut bytes, &entry.data);
+    }
+    bytes
+}
+
+pub(crate) fn decode(mut bytes: &[u8]) -> Result<Vec<SnapshotEntry>, AssetError> {
+    if take(&mut bytes, MAGIC.len())? != MAGIC {
+        return Err(corrupt());
+    }
+    if take_u64(&mut bytes)? != VERSION {
+        return Err(AssetError::Parse(
+            "unsupported snapshot version".to_string(),
+        ));
+    }
+
+    let count = take_u64(&mut bytes)?;
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        let ty = take_string(&mut bytes)?;
+        let id = take_u64(&mut bytes)?;
+        let path = Some(take_bytes(&mut bytes)?)
+            .filter(|path| !path.is_empty())
+            .map(bytes_path);
+        let modified = match take(&mut bytes, 1)?[0] {
+            0 => None,
+            _ => {
+                let secs = take_u64(&mut bytes)?;
+                let nanos = take_u64(&mut bytes)?;
+                let since_epoch = Duration::from_secs(secs)
+                    .checked_add(Duration::from_nanos(nanos))
+                    .ok_or_else(corrupt)?;
+                Some(UNIX_EPOCH.checked_add(since_epoch).ok_or_else(corrupt)?)
+            }
+        };
+        let data = take_bytes(&mut bytes)?.to_vec();
+        entries.push(SnapshotEntry {
+            ty,
+            id,
+            path,
+            modified,
+            data,
+        });
+    }
+    Ok(entries)
+}
+
+fn put_u64(bytes: &mut Vec<u8>, value: u64) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_bytes(bytes: &mut Vec<u8>, value: &[u8]) {
+    put_u64(bytes, value.len() as u64);
+    bytes.extend_from_slice(value);
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], AssetError> {
+    if bytes.len() < len {
+        return Err(corrupt());
+    }
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+    Ok(head)
+}
+
+fn take_u64(bytes: &mut &[u8]) -> Result<u64, AssetError> {
+    let value = take(bytes, 8)?;
+    Ok(u64::from_le_bytes(
+        value.try_into().expect("slice has 8 bytes"),
+    ))
+}
+
+fn take_bytes<'a>(bytes: &mut &'a [u8]) -> Result<&'a [u8], AssetError> {
+    let len = take_u64(bytes)?;
+    take(bytes, usize::try_from(len).map_err(|_| corrupt())?)
+}
+
+fn take_string(bytes: &mut &[u8]) -> Result<String, AssetError> {
+    String::from_utf8(take_bytes(bytes)?.to_vec()).map_err(|_| corrupt())
+}
+
+// paths are stored as raw bytes where possible, so non utf-8 paths survive a round-trip
+#[cfg(unix)]
+fn path_bytes(path: Option<&Path>) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.map(|path| path.as_os_str().as_bytes().to_vec())
+        .unwrap_or_default()
+}
+
+#[cfg(not(unix))]
+fn path_bytes(path: Option<&Path>) -> Vec<u8> {
+    path.map(|path| path.to_string_lossy().into_owned().into_bytes())
+        .unwrap_or_default()
+}
+
+#[cfg(unix)]
+fn bytes_path(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+fn bytes_path(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+fn corrupt() -> AssetError {
+    AssetError::Parse("corrupt snapshot".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(modified: Option<SystemTime>) -> SnapshotEntry {
+        SnapshotEntry {
+            ty: "text".to_string(),
+            id: 7,
+            path: Some(PathBuf::from("assets/a.txt")),
+            modified,
+            data: b"data".to_vec(),
+        }
+    }
+
+    #[test]
+    fn decode_reads_what_encode_wrote() {
+        let modified = UNIX_EPOCH + Duration::new(1_700_000_000, 123);
+        let bytes = encode(&[entry(Some(modified)), entry(None)]);
+
+        let entries = decode(&bytes).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].ty, "text");
+        assert_eq!(entries[0].id, 7);
+        assert_eq!(entries[0].path.as_deref(), Some(Path::new("assets/a.txt")));
+        assert_eq!(entries[0].modified, Some(modified));
+        assert_eq!(entries[0].data, b"data");
+        assert_eq!(entries[1].modified, None);
+    }
+
+    #[test]
+    fn truncated_snapshots_are_corrupt() {
+        let bytes = encode(&[entry(None)]);
+        assert!(matches!(
+            decode(&bytes[..bytes.len() - 1]),
+            Err(AssetError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn out_of_range_times_are_corrupt() {
+        let mut bytes = encode(&[entry(Some(UNIX_EPOCH))]);
+        // overwrite the seconds of the modification time with the largest value
+        let secs = MAGIC.len() + 8 * 2 + (8 + 4) + 8 + (8 + 12) + 1;
+        bytes[secs..secs + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        bytes[secs + 8..secs + 16].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(decode(&bytes), Err(AssetError::Parse(_))));
+    }
+}
@@ -0,0 +1,189 @@
+use crate::PreprocessorFn;
+use std::{
+    cell::RefCell,
+    fs,
+    io::{self, Read, Seek, SeekFrom},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{Arc, mpsc},
+};
+
+/// Where Assets reads files from, see [`crate::Assets::set_source`]
+///
+/// E.g. an archive, a http server or an in memory map in tests. Loaders reach the
+/// source through [`read_file`], loaders reading `std::fs` themselves bypass it
+pub trait AssetSource: Send + Sync {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// Get the file on disk the path is read from, if any
+    ///
+    /// Local files are hot reloaded by the file watcher of Assets instead of [`AssetSource::watch`]
+    fn local_path(&self, _path: &Path) -> Option<PathBuf> {
+        None
+    }
+
+    /// Send the path on `changed` whenever the file changes
+    ///
+    /// Sources which can not detect changes keep the default and are never reloaded
+    fn watch(&self, _path: &Path, _changed: mpsc::Sender<PathBuf>) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Get the source as a [`RangeReadableSource`] if it can read parts of a file
+    fn as_range_readable(&self) -> Option<&dyn RangeReadableSource> {
+        None
+    }
+}
+
+/// Source which can read a byte range of a file without reading the rest,
+/// used by [`crate::Assets::load_ranged`]
+///
+/// E.g. a packed archive with an index
+pub trait RangeReadableSource: AssetSource {
+    fn read_range(&self, path: &Path, range: Range<u64>) -> io::Result<Vec<u8>>;
+}
+
+/// Source reading from the file system
+///
+/// Changes are picked up by the file watcher of Assets
+#[derive(Default)]
+pub struct FsSource;
+
+impl FsSource {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl AssetSource for FsSource {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn local_path(&self, path: &Path) -> Option<PathBuf> {
+        Some(path.to_path_buf())
+    }
+
+    fn as_range_readable(&self) -> Option<&dyn RangeReadableSource> {
+        Some(self)
+    }
+}
+
+impl RangeReadableSource for FsSource {
+    fn read_range(&self, path: &Path, range: Range<u64>) -> io::Result<Vec<u8>> {
+        let mut file = fs::File::open(path)?;
+        file.seek(SeekFrom::Start(range.start))?;
+        let mut bytes = Vec::new();
+        file.take(range.end.saturating_sub(range.start))
+            .read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+// how the Assets running a loader reads files
+#[derive(Clone)]
+pub(crate) struct LoadContext {
+    pub(crate) source: Arc<dyn AssetSource>,
+    pub(crate) preprocessor: Option<PreprocessorFn>,
+}
+
+thread_local! {
+    // context of the Assets running a loader on this thread
+    static CURRENT: RefCell<Option<LoadContext>> = const { RefCell::new(None) };
+}
+
+/// Read a file through the source and preprocessor of the Assets loading it
+///
+/// Loaders call this instead of `std::fs::read` to work with any source.
+/// Outside of a load the file system is read as is
+pub fn read_file(path: &Path) -> io::Result<Vec<u8>> {
+    let Some(context) = CURRENT.with_borrow(Option::clone) else {
+        return fs::read(path);
+    };
+    let bytes = context.source.read(path)?;
+    Ok(match &context.preprocessor {
+        Some(preprocessor) => preprocessor(path, bytes),
+        None => bytes,
+    })
+}
+
+/// Read a utf-8 file through the source of the Assets loading it, see [`read_file`]
+pub fn read_file_to_string(path: &Path) -> io::Result<String> {
+    String::from_utf8(read_file(path)?)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+// run a loader with read_file going through the context
+pub(crate) fn with_context<R>(context: &LoadContext, load: impl FnOnce() -> R) -> R {
+    // restore the outer context even if the loader panics
+    struct Restore(Option<LoadContext>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            CURRENT.set(self.0.take());
+        }
+    }
+
+    let _restore = Restore(CURRENT.replace(Some(context.clone())));
+    load()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fs_read_range_returns_the_slice() {
+        let path = std::env::temp_dir().join(format!("assets-range-{}", std::process::id()));
+        std::fs::write(&path, "0123456789").unwrap();
+
+        assert_eq!(FsSource.read_range(&path, 2..5).unwrap(), b"234");
+        assert_eq!(FsSource.read_range(&path, 8..20).unwrap(), b"89");
+        assert!(FsSource.as_range_readable().is_some());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    struct Fixed;
+
+    impl AssetSource for Fixed {
+        fn read(&self, _path: &Path) -> io::Result<Vec<u8>> {
+            Ok(b"fixed".to_vec())
+        }
+    }
+
+    #[test]
+    fn read_file_goes_through_the_current_source() {
+        let path = std::env::temp_dir().join(format!("assets-read-{}", std::process::id()));
+        std::fs::write(&path, "on disk").unwrap();
+        let context = LoadContext {
+            source: Arc::new(Fixed),
+            preprocessor: None,
+        };
+
+        assert_eq!(
+            with_context(&context, || read_file(&path).unwrap()),
+            b"fixed"
+        );
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            with_context(&context, || panic!("loader panicked"))
+        }));
+        assert!(panicked.is_err());
+        assert_eq!(read_file_to_string(&path).unwrap(), "on disk");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn read_file_applies_the_preprocessor_after_the_source() {
+        let path = Path::new("shader.txt");
+        let context = LoadContext {
+            source: Arc::new(Fixed),
+            preprocessor: Some(Arc::new(|path: &Path, mut bytes: Vec<u8>| {
+                bytes.extend_from_slice(path.to_string_lossy().as_bytes());
+                bytes
+            })),
+        };
+
+        let bytes = with_context(&context, || read_file(path).unwrap());
+        assert_eq!(bytes, b"fixedshader.txt");
+    }
+}